@@ -1,18 +1,26 @@
 use std::time::Duration;
 
 use clap::Parser;
+use futures::SinkExt;
 use lighthouse_client::{protocol::{Authentication, Frame}, Lighthouse, Result, TokioWebSocket, LIGHTHOUSE_URL};
 use tokio::time::{self, Instant};
 use tracing::info;
 
-async fn run(lh: Lighthouse<TokioWebSocket>, delay_ms: Option<u64>) -> Result<()> {
+async fn run(lh: Lighthouse<TokioWebSocket>, delay_ms: Option<u64>, sink: bool) -> Result<()> {
     info!("Connected to the Lighthouse server");
 
+    let mut frame_sink = sink.then(|| lh.frame_sink(None));
     let mut last_second = Instant::now();
     let mut frames_per_second = 0;
 
     loop {
-        lh.put_model(Frame::fill(rand::random())).await?;
+        let frame = Frame::fill(rand::random());
+        if let Some(frame_sink) = &mut frame_sink {
+            // Only submits the frame, superseded frames are dropped
+            frame_sink.send(frame).await?;
+        } else {
+            lh.put_model(frame).await?;
+        }
         frames_per_second += 1;
         // Interestingly, the loop is vastly quicker when running no delay (6k
         // fps in debug, 20k fps in release mode) compared to running with a
@@ -43,6 +51,9 @@ struct Args {
     /// The delay in ms between successive requests.
     #[arg(short, long)]
     delay_ms: Option<u64>,
+    /// Whether to send frames through a latest-wins frame sink.
+    #[arg(long)]
+    sink: bool,
 }
 
 #[tokio::main(flavor = "multi_thread")]
@@ -54,5 +65,5 @@ async fn main() -> Result<()> {
     let auth = Authentication::new(&args.username, &args.token);
     let lh = Lighthouse::connect_with_tokio_to(&args.url, auth).await?;
 
-    run(lh, args.delay_ms).await
+    run(lh, args.delay_ms, args.sink).await
 }
@@ -0,0 +1,163 @@
+use std::fmt;
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{future::BoxFuture, stream, FutureExt, Sink, Stream, StreamExt};
+use lighthouse_protocol::{ResourcePath, ServerMessage, Value, Verb};
+use serde::Serialize;
+
+use crate::{lighthouse::into_path, Error, Lighthouse, RequestOptions, Result};
+
+/// The number of requests a batch keeps in flight by default.
+pub const DEFAULT_BATCH_WINDOW: usize = 16;
+
+/// A request of a batch, performed with the batch's options.
+type BatchRequest<S> = Box<dyn FnOnce(Lighthouse<S>, RequestOptions) -> BoxFuture<'static, Result<ServerMessage<Value>>> + Send>;
+
+/// A batch of requests that are pipelined over the connection, obtained via
+/// [`Lighthouse::batch`].
+///
+/// Rather than waiting for every response before sending the next request,
+/// up to [`Batch::window`] requests are kept in flight at a time. The
+/// connection-wide limit set via
+/// [`LighthouseBuilder::max_in_flight`](crate::LighthouseBuilder::max_in_flight)
+/// applies on top of that. The results are returned in the order in which
+/// the requests were added, failed requests do not abort the batch.
+///
+/// ```no_run
+/// # use lighthouse_client::{Lighthouse, TokioWebSocket};
+/// # async fn run(lh: Lighthouse<TokioWebSocket>) -> lighthouse_client::Result<()> {
+/// let mut batch = lh.batch().mkdir("/test");
+/// for i in 0..100 {
+///     batch = batch.post(format!("/test/{i}"), i);
+/// }
+/// for result in batch.send().await {
+///     result?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Batch<S> {
+    lh: Lighthouse<S>,
+    window: usize,
+    options: RequestOptions,
+    requests: Vec<BatchRequest<S>>,
+}
+
+impl<S> Batch<S> {
+    /// Creates an empty batch.
+    pub(crate) fn new(lh: Lighthouse<S>) -> Self {
+        Self {
+            lh,
+            window: DEFAULT_BATCH_WINDOW,
+            options: RequestOptions::default(),
+            requests: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum number of requests of this batch that are in flight
+    /// at a time. Defaults to [`DEFAULT_BATCH_WINDOW`].
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self
+    }
+
+    /// Sets the options for the requests of this batch, overriding the
+    /// default options of the connection.
+    pub fn options(mut self, options: RequestOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// The number of requests in this batch.
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Whether this batch contains no requests.
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+}
+
+impl<S> Batch<S>
+    where S: Stream<Item = tungstenite::Result<Message>>
+           + Sink<Message, Error = tungstenite::Error>
+           + Send
+           + 'static {
+    /// Adds a request to the given path with the given payload. Streaming
+    /// is not supported.
+    pub fn perform<P>(mut self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Self
+    where
+        P: Serialize + Send + Sync + 'static {
+        // Invalid paths fail the request rather than the batch
+        let (verb, path) = (verb.clone(), into_path(path));
+        self.requests.push(Box::new(move |lh, options| async move {
+            lh.perform_with_options(&verb, path?, payload, &options).await
+        }.boxed()));
+        self
+    }
+
+    /// Adds a POST, combining PUT and CREATE.
+    pub fn post<P>(self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Self
+    where
+        P: Serialize + Send + Sync + 'static {
+        self.perform(&Verb::Post, path, payload)
+    }
+
+    /// Adds a PUT, updating the resource at the given path.
+    pub fn put<P>(self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Self
+    where
+        P: Serialize + Send + Sync + 'static {
+        self.perform(&Verb::Put, path, payload)
+    }
+
+    /// Adds a CREATE, creating a resource at the given path.
+    pub fn create(self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Self {
+        self.perform(&Verb::Create, path, ())
+    }
+
+    /// Adds a DELETE, deleting the resource at the given path.
+    pub fn delete(self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Self {
+        self.perform(&Verb::Delete, path, ())
+    }
+
+    /// Adds a MKDIR, creating a directory at the given path.
+    pub fn mkdir(self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Self {
+        self.perform(&Verb::Mkdir, path, ())
+    }
+
+    /// Adds a LIST, listing the directory tree at the given path.
+    pub fn list(self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Self {
+        self.perform(&Verb::List, path, ())
+    }
+
+    /// Adds a GET, fetching the resource at the given path. The payload of
+    /// the response can be decoded via [`ServerMessage::decode_payload`].
+    pub fn get(self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Self {
+        self.perform(&Verb::Get, path, ())
+    }
+
+    /// Sends the requests and waits for all responses, which are returned
+    /// in the order the requests were added.
+    pub async fn send(self) -> Vec<Result<ServerMessage<Value>>> {
+        let Self { lh, window, options, requests } = self;
+        // Futures are lazy, so requests are only sent once polled by the stream
+        let requests: Vec<_> = requests.into_iter()
+            .map(|request| request(lh.clone(), options.clone()))
+            .collect();
+        stream::iter(requests)
+            .buffered(window)
+            .collect()
+            .await
+    }
+}
+
+impl<S> fmt::Debug for Batch<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Batch")
+            .field("window", &self.window)
+            .field("options", &self.options)
+            .field("requests", &self.requests.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,666 @@
+//! A synchronous client for programs that do not want to use an async
+//! runtime, e.g. scripts and command line tools.
+//!
+//! The [`Lighthouse`] in this module mirrors the methods of the async
+//! [`Lighthouse`](crate::Lighthouse), but blocks the calling thread until
+//! they complete. Streams are exposed as iterators. The connection is
+//! driven by a private runtime on a background thread, which is shared by
+//! all blocking clients. The blocking methods must not be called from
+//! within an async context. Typed resource handles and frame sinks have
+//! blocking counterparts in [`Resource`] and [`FrameSink`].
+//!
+//! ```no_run
+//! # use lighthouse_client::{blocking::Lighthouse, protocol::{Authentication, Color, Frame}};
+//! # fn main() -> lighthouse_client::Result<()> {
+//! let lh = Lighthouse::connect(Authentication::new("user", "token"))?;
+//! lh.put_model(Frame::fill(Color::RED))?;
+//! for event in lh.stream_input()? {
+//!     println!("Got {:?}", event?.payload);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, future::Future, marker::PhantomData, panic, pin::pin, sync::OnceLock, thread, time::Duration};
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{channel::{mpsc, oneshot}, executor, future::{self, BoxFuture}, stream::BoxStream, FutureExt, SinkExt, Sink, Stream, StreamExt};
+use lighthouse_protocol::{Authentication, DirectoryTree, Frame, InputEvent, LaserMetrics, Model, ResourcePath, ServerMessage, Value, Verb};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::{self, Handle};
+
+use crate::{lighthouse::into_path, Batch, ConnectionState, EarlyMessageLimits, Error, Keepalive, LighthouseBuilder, RequestOptions, Result, SessionRecorder, Spawner, Stats, StreamEvent, TokioWebSocket, LIGHTHOUSE_URL};
+
+/// The asynchronous client wrapped by the blocking one.
+type AsyncLighthouse<S> = crate::Lighthouse<S>;
+
+/// The asynchronous frame sink wrapped by the blocking one.
+type AsyncFrameSink = crate::FrameSink;
+
+/// Fetches the handle of the runtime driving all blocking clients, starting
+/// it on a background thread if needed.
+fn runtime() -> &'static Handle {
+    static RUNTIME: OnceLock<Handle> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .expect("Could not create the runtime for the blocking client");
+        let handle = runtime.handle().clone();
+        thread::Builder::new()
+            .name("lighthouse-blocking".to_owned())
+            .spawn(move || runtime.block_on(future::pending::<()>()))
+            .expect("Could not start the runtime for the blocking client");
+        handle
+    })
+}
+
+/// Runs the given future on the runtime, blocking until it completes.
+fn block_on<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static {
+    match executor::block_on(runtime().spawn(future)) {
+        Ok(output) => output,
+        Err(error) => panic::resume_unwind(error.into_panic()),
+    }
+}
+
+/// A spawner creating tasks on the runtime of the blocking clients.
+enum BlockingSpawner {}
+
+impl Spawner for BlockingSpawner {
+    fn spawn<F>(future: F) where F: Future + Send + 'static, F::Output: Send {
+        runtime().spawn(future);
+    }
+}
+
+/// A blocking connection to the lighthouse server, see the
+/// [module documentation](self). Cloning is cheap and yields a handle to
+/// the same connection.
+pub struct Lighthouse<S = TokioWebSocket> {
+    inner: AsyncLighthouse<S>,
+}
+
+/// An iterator over the items of a stream, e.g. input events. Dropping it
+/// stops the stream.
+pub struct StreamIter<T> {
+    items: mpsc::Receiver<T>,
+    /// Stops the task forwarding the items once dropped.
+    _cancel: oneshot::Sender<()>,
+}
+
+impl<T> Iterator for StreamIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        executor::block_on(self.items.next())
+    }
+}
+
+/// A blocking handle to the resource at a fixed path with a fixed payload
+/// type, obtained via [`Lighthouse::resource`]. Mirrors the async
+/// [`Resource`](crate::Resource).
+pub struct Resource<S, T> {
+    lh: Lighthouse<S>,
+    path: ResourcePath,
+    payload: PhantomData<fn(T) -> T>,
+}
+
+/// A blocking sink that sends frames to the user's lighthouse model,
+/// obtained via [`Lighthouse::frame_sink`]. Mirrors the async
+/// [`FrameSink`](crate::FrameSink), i.e. superseded frames are dropped
+/// rather than queued.
+pub struct FrameSink {
+    inner: AsyncFrameSink,
+}
+
+impl Lighthouse<TokioWebSocket> {
+    /// Connects to the lighthouse server at the given URL, see
+    /// [`Lighthouse::connect_with_tokio_to`](crate::Lighthouse::connect_with_tokio_to).
+    pub fn connect_to(url: &str, authentication: Authentication) -> Result<Self> {
+        LighthouseBuilder::new(authentication).url(url).connect_blocking()
+    }
+
+    /// Connects to the lighthouse server at the default URL.
+    pub fn connect(authentication: Authentication) -> Result<Self> {
+        Self::connect_to(LIGHTHOUSE_URL, authentication)
+    }
+}
+
+impl LighthouseBuilder {
+    /// Connects to the lighthouse, returning a blocking client.
+    pub fn connect_blocking(self) -> Result<Lighthouse> {
+        let builder = self.spawner::<BlockingSpawner>();
+        let inner = block_on(builder.connect_with_tokio())?;
+        Ok(Lighthouse { inner })
+    }
+
+    /// Creates a blocking client from an already established WebSocket, see
+    /// [`LighthouseBuilder::build`].
+    pub fn build_blocking<S>(self, web_socket: S) -> Result<Lighthouse<S>>
+    where
+        S: Stream<Item = tungstenite::Result<Message>>
+         + Sink<Message, Error = tungstenite::Error>
+         + Send
+         + 'static {
+        let builder = self.spawner::<BlockingSpawner>();
+        // Spawning the receive loop requires the runtime to be running
+        runtime();
+        Ok(Lighthouse { inner: builder.build(web_socket)? })
+    }
+}
+
+impl<S> Lighthouse<S>
+    where S: Stream<Item = tungstenite::Result<Message>>
+           + Sink<Message, Error = tungstenite::Error>
+           + Send
+           + 'static {
+    /// Runs the given operation on the async client, blocking until it
+    /// completes.
+    fn run<T, F>(&self, operation: F) -> T
+    where
+        F: for<'a> FnOnce(&'a AsyncLighthouse<S>) -> BoxFuture<'a, T> + Send + 'static,
+        T: Send + 'static {
+        let lh = self.inner.clone();
+        block_on(async move { operation(&lh).await })
+    }
+
+    /// Opens a stream on the async client and forwards its items to an
+    /// iterator. The stream is dropped along with the iterator.
+    fn open<T, F>(&self, open: F) -> Result<StreamIter<T>>
+    where
+        F: for<'a> FnOnce(&'a AsyncLighthouse<S>) -> BoxFuture<'a, Result<BoxStream<'a, T>>> + Send + 'static,
+        T: Send + 'static {
+        let lh = self.inner.clone();
+        let (opened_tx, opened_rx) = oneshot::channel();
+        let (mut items_tx, items_rx) = mpsc::channel(0);
+        let (cancel_tx, cancel_rx) = oneshot::channel::<()>();
+        runtime().spawn(async move {
+            let mut stream = match open(&lh).await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    let _ = opened_tx.send(Err(error));
+                    return;
+                },
+            };
+            let _ = opened_tx.send(Ok(()));
+            let forward = async {
+                while let Some(item) = stream.next().await {
+                    if items_tx.send(item).await.is_err() {
+                        break;
+                    }
+                }
+            };
+            future::select(pin!(forward), cancel_rx).await;
+        });
+        executor::block_on(opened_rx).map_err(|_| Error::ConnectionClosed)??;
+        Ok(StreamIter { items: items_rx, _cancel: cancel_tx })
+    }
+
+    /// Creates a handle to the resource at the given path with the given
+    /// payload type.
+    pub fn resource<T>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<Resource<S, T>> {
+        Ok(Resource { lh: self.clone(), path: into_path(path)?, payload: PhantomData })
+    }
+
+    /// Creates a handle to the user's lighthouse model. Fails if the
+    /// username is not a valid path segment.
+    pub fn model(&self) -> Result<Resource<S, Model>> {
+        Ok(Resource { lh: self.clone(), path: self.inner.model()?.path().clone(), payload: PhantomData })
+    }
+
+    /// Creates a handle to the user's input endpoint. Fails if the username
+    /// is not a valid path segment.
+    pub fn input(&self) -> Result<Resource<S, InputEvent>> {
+        Ok(Resource { lh: self.clone(), path: self.inner.input()?.path().clone(), payload: PhantomData })
+    }
+
+    /// Creates a sink sending frames to the user's lighthouse model,
+    /// optionally capping the rate at the given number of frames per second.
+    pub fn frame_sink(&self, max_fps: Option<f64>) -> FrameSink {
+        FrameSink { inner: self.inner.frame_sink(max_fps) }
+    }
+
+    /// Replaces the user's lighthouse model with the given frame.
+    pub fn put_model(&self, frame: Frame) -> Result<ServerMessage<()>> {
+        self.run(move |lh| lh.put_model(frame).boxed())
+    }
+
+    /// Replaces the user's lighthouse model with the given frame without
+    /// waiting for the server's response.
+    pub fn put_model_nowait(&self, frame: Frame) -> Result<()> {
+        self.run(move |lh| lh.put_model_nowait(frame).boxed())
+    }
+
+    /// Requests a stream of events (including key/controller events) for the user's lighthouse model.
+    pub fn stream_model(&self) -> Result<StreamIter<Result<ServerMessage<Model>>>> {
+        self.open(move |lh| async move { Ok(lh.stream_model().await?.boxed()) }.boxed())
+    }
+
+    /// Sends an input event to the user's input endpoint.
+    pub fn put_input(&self, payload: InputEvent) -> Result<ServerMessage<()>> {
+        self.run(move |lh| lh.put_input(payload).boxed())
+    }
+
+    /// Streams input events from the user's input endpoint.
+    pub fn stream_input(&self) -> Result<StreamIter<Result<ServerMessage<InputEvent>>>> {
+        self.open(move |lh| async move { Ok(lh.stream_input().await?.boxed()) }.boxed())
+    }
+
+    /// Streams input events from the user's input endpoint, yielding a
+    /// [`StreamEvent::Gap`] whenever the connection was re-established.
+    pub fn stream_input_with_gaps(&self) -> Result<StreamIter<Result<StreamEvent<InputEvent>>>> {
+        self.open(move |lh| async move { Ok(lh.stream_input_with_gaps().await?.boxed()) }.boxed())
+    }
+
+    /// Fetches lamp server metrics.
+    pub fn get_laser_metrics(&self) -> Result<ServerMessage<LaserMetrics>> {
+        self.run(move |lh| lh.get_laser_metrics().boxed())
+    }
+
+    /// Combines PUT and CREATE. Requires CREATE and WRITE permission.
+    pub fn post<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<()>>
+    where
+        P: Serialize + Send + Sync + 'static {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.post(path, payload).boxed())
+    }
+
+    /// Updates the resource at the given path with the given payload. Requires WRITE permission.
+    pub fn put<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<()>>
+    where
+        P: Serialize + Send + Sync + 'static {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.put(path, payload).boxed())
+    }
+
+    /// Updates the resource at the given path with the given payload without
+    /// waiting for the server's response.
+    pub fn put_nowait<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<()>
+    where
+        P: Serialize + Send + Sync + 'static {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.put_nowait(path, payload).boxed())
+    }
+
+    /// Creates a resource at the given path. Requires CREATE permission.
+    pub fn create(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.create(path).boxed())
+    }
+
+    /// Deletes a resource at the given path. Requires DELETE permission.
+    pub fn delete(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.delete(path).boxed())
+    }
+
+    /// Creates a directory at the given path. Requires CREATE permission.
+    pub fn mkdir(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.mkdir(path).boxed())
+    }
+
+    /// Lists the directory tree at the given path. Requires READ permission.
+    pub fn list(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<DirectoryTree>> {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.list(path).boxed())
+    }
+
+    /// Gets the resource at the given path. Requires READ permission.
+    pub fn get<R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<R>>
+    where
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.get(path).boxed())
+    }
+
+    /// Links the given source to the given destination path.
+    pub fn link(&self, src_path: impl TryInto<ResourcePath, Error: Into<Error>>, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let (src_path, dest_path) = (into_path(src_path)?, into_path(dest_path)?);
+        self.run(move |lh| lh.link(src_path, dest_path).boxed())
+    }
+
+    /// Unlinks the given source from the given destination path.
+    pub fn unlink(&self, src_path: impl TryInto<ResourcePath, Error: Into<Error>>, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let (src_path, dest_path) = (into_path(src_path)?, into_path(dest_path)?);
+        self.run(move |lh| lh.unlink(src_path, dest_path).boxed())
+    }
+
+    /// Performs a single request to the given path with the given payload.
+    pub fn perform<P, R>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<R>>
+    where
+        P: Serialize + Send + Sync + 'static,
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        self.perform_with_options(verb, path, payload, &RequestOptions::default())
+    }
+
+    /// Performs a single request to the given path with the given payload,
+    /// overriding the default options with the given ones.
+    pub fn perform_with_options<P, R>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<ServerMessage<R>>
+    where
+        P: Serialize + Send + Sync + 'static,
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        let (verb, path, options) = (verb.clone(), into_path(path)?, options.clone());
+        self.run(move |lh| async move { lh.perform_with_options(&verb, path, payload, &options).await }.boxed())
+    }
+
+    /// Performs a single request to the given path with the given payload
+    /// without waiting for the server's response, see
+    /// [`Lighthouse::unacknowledged_errors`].
+    pub fn perform_nowait<P>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<()>
+    where
+        P: Serialize + Send + Sync + 'static {
+        let (verb, path) = (verb.clone(), into_path(path)?);
+        self.run(move |lh| async move { lh.perform_nowait(&verb, path, payload).await }.boxed())
+    }
+
+    /// Performs a STREAM request to the given path with the given payload.
+    /// Automatically sends a STOP once dropped.
+    pub fn stream<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<StreamIter<Result<ServerMessage<R>>>>
+    where
+        P: Serialize + Send + Sync + 'static,
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        self.stream_with_options(path, payload, &RequestOptions::default())
+    }
+
+    /// Performs a STREAM request to the given path with the given payload,
+    /// overriding the default options with the given ones. Automatically
+    /// sends a STOP once dropped.
+    pub fn stream_with_options<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<StreamIter<Result<ServerMessage<R>>>>
+    where
+        P: Serialize + Send + Sync + 'static,
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        let (path, options) = (into_path(path)?, options.clone());
+        self.open(move |lh| async move { Ok(lh.stream_with_options(path, payload, &options).await?.boxed()) }.boxed())
+    }
+
+    /// Performs a STREAM request to the given path with the given payload,
+    /// yielding a [`StreamEvent::Gap`] whenever the connection was
+    /// re-established. Automatically sends a STOP once dropped.
+    pub fn stream_with_gaps<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<StreamIter<Result<StreamEvent<R>>>>
+    where
+        P: Serialize + Send + Sync + 'static,
+        R: for<'de> Deserialize<'de> + Send + 'static {
+        let (path, options) = (into_path(path)?, options.clone());
+        self.open(move |lh| async move { Ok(lh.stream_with_gaps(path, payload, &options).await?.boxed()) }.boxed())
+    }
+
+    /// Stops the stream with the given request id at the given path.
+    pub fn stop(&self, request_id: i32, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        let path = into_path(path)?;
+        self.run(move |lh| lh.stop(request_id, path).boxed())
+    }
+
+    /// Creates an empty batch of requests, see [`Batch`]. Batches are sent
+    /// via [`Lighthouse::send_batch`].
+    pub fn batch(&self) -> Batch<S> {
+        self.inner.batch()
+    }
+
+    /// Sends the given batch and waits for all responses, which are
+    /// returned in the order the requests were added.
+    pub fn send_batch(&self, batch: Batch<S>) -> Vec<Result<ServerMessage<Value>>> {
+        block_on(batch.send())
+    }
+
+    /// Iterates over the errors from failed requests that were sent without
+    /// waiting for the response.
+    pub fn unacknowledged_errors(&self) -> StreamIter<Error> {
+        self.subscribe(move |lh| lh.unacknowledged_errors().boxed())
+    }
+
+    /// Iterates over the successful responses carrying warnings.
+    pub fn warnings(&self) -> StreamIter<ServerMessage<()>> {
+        self.subscribe(move |lh| lh.warnings().boxed())
+    }
+
+    /// Iterates over the messages pushed by the server without a request id.
+    pub fn notifications(&self) -> StreamIter<ServerMessage<Value>> {
+        self.subscribe(move |lh| lh.notifications().boxed())
+    }
+
+    /// Iterates over the states of the connection, starting with the
+    /// current state. The iterator ends once the connection is closed.
+    pub fn state(&self) -> StreamIter<ConnectionState> {
+        self.subscribe(move |lh| lh.state().boxed())
+    }
+
+    /// Subscribes to a stream that cannot fail to open.
+    fn subscribe<T, F>(&self, subscribe: F) -> StreamIter<T>
+    where
+        F: for<'a> FnOnce(&'a AsyncLighthouse<S>) -> BoxStream<'a, T> + Send + 'static,
+        T: Send + 'static {
+        self.open(move |lh| future::ready(Ok(subscribe(lh))).boxed())
+            .expect("Subscribing does not fail")
+    }
+
+    /// Fetches the current state of the connection.
+    pub fn current_state(&self) -> ConnectionState {
+        self.inner.current_state()
+    }
+
+    /// Takes a snapshot of the connection's statistics.
+    pub fn stats(&self) -> Stats {
+        self.inner.stats()
+    }
+
+    /// Fetches the round-trip latency measured by the most recent keepalive
+    /// ping, if any.
+    pub fn latency(&self) -> Option<Duration> {
+        self.inner.latency()
+    }
+
+    /// Fetches the credentials used to authenticate with the lighthouse.
+    pub fn authentication(&self) -> &Authentication {
+        self.inner.authentication()
+    }
+
+    /// Sets the limits for messages whose request id has no registered slot.
+    pub fn set_early_message_limits(&self, limits: EarlyMessageLimits) {
+        self.inner.set_early_message_limits(limits);
+    }
+
+    /// Configures the keepalive pings, `None` disables pings.
+    pub fn set_keepalive(&self, keepalive: Option<Keepalive>) {
+        self.inner.set_keepalive(keepalive);
+    }
+
+    /// Starts recording the exchanged messages, `None` stops recording.
+    pub fn set_recorder(&self, recorder: Option<SessionRecorder>) {
+        self.inner.set_recorder(recorder);
+    }
+
+    /// Sets the maximum number of requests awaiting their response, `None`
+    /// removes the limit and a limit of 0 is treated as 1.
+    pub fn set_max_in_flight(&self, max_in_flight: Option<usize>) {
+        self.inner.set_max_in_flight(max_in_flight);
+    }
+
+    /// Fetches the maximum number of requests awaiting their response.
+    pub fn max_in_flight(&self) -> Option<usize> {
+        self.inner.max_in_flight()
+    }
+
+    /// Fetches the default timeout for requests.
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.inner.default_timeout()
+    }
+
+    /// Sets the default timeout for requests made through any handle to
+    /// this connection.
+    pub fn set_default_timeout(&self, timeout: Option<Duration>) {
+        self.inner.set_default_timeout(timeout);
+    }
+
+    /// Closes the WebSocket connection gracefully with a close message.
+    pub fn close(&self) -> Result<()> {
+        self.run(move |lh| lh.close().boxed())
+    }
+}
+
+impl<S> Clone for Lighthouse<S> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<S, T> Resource<S, T> {
+    /// The path of the resource.
+    pub fn path(&self) -> &ResourcePath {
+        &self.path
+    }
+}
+
+impl<S, T> Resource<S, T>
+    where S: Stream<Item = tungstenite::Result<Message>>
+           + Sink<Message, Error = tungstenite::Error>
+           + Send
+           + 'static,
+          T: Serialize + for<'de> Deserialize<'de> + Send + Sync + 'static {
+    /// Gets the resource. Requires READ permission.
+    pub fn get(&self) -> Result<ServerMessage<T>> {
+        self.lh.get(&self.path)
+    }
+
+    /// Updates the resource with the given payload. Requires WRITE permission.
+    pub fn put(&self, payload: T) -> Result<ServerMessage<()>> {
+        self.lh.put(&self.path, payload)
+    }
+
+    /// Updates the resource with the given payload without waiting for the
+    /// server's response.
+    pub fn put_nowait(&self, payload: T) -> Result<()> {
+        self.lh.put_nowait(&self.path, payload)
+    }
+
+    /// Combines PUT and CREATE. Requires CREATE and WRITE permission.
+    pub fn post(&self, payload: T) -> Result<ServerMessage<()>> {
+        self.lh.post(&self.path, payload)
+    }
+
+    /// Creates the resource. Requires CREATE permission.
+    pub fn create(&self) -> Result<ServerMessage<()>> {
+        self.lh.create(&self.path)
+    }
+
+    /// Deletes the resource. Requires DELETE permission.
+    pub fn delete(&self) -> Result<ServerMessage<()>> {
+        self.lh.delete(&self.path)
+    }
+
+    /// Streams the resource. Automatically sends a STOP once dropped.
+    pub fn stream(&self) -> Result<StreamIter<Result<ServerMessage<T>>>> {
+        self.lh.stream(&self.path, ())
+    }
+
+    /// Streams the resource with the given options, yielding a
+    /// [`StreamEvent::Gap`] whenever the connection was re-established.
+    pub fn stream_with_gaps(&self, options: &RequestOptions) -> Result<StreamIter<Result<StreamEvent<T>>>> {
+        self.lh.stream_with_gaps(&self.path, (), options)
+    }
+
+    /// Links this resource to the given destination, i.e. forwards its
+    /// updates to the destination.
+    pub fn link(&self, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.lh.link(&self.path, dest_path)
+    }
+
+    /// Unlinks this resource from the given destination.
+    pub fn unlink(&self, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.lh.unlink(&self.path, dest_path)
+    }
+}
+
+impl<S, T> Clone for Resource<S, T> {
+    fn clone(&self) -> Self {
+        Self { lh: self.lh.clone(), path: self.path.clone(), payload: PhantomData }
+    }
+}
+
+impl<S, T> fmt::Debug for Resource<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resource")
+            .field("path", &self.path)
+            .field("payload", &std::any::type_name::<T>())
+            .finish()
+    }
+}
+
+impl FrameSink {
+    /// Hands the given frame to the background sender, replacing any frame
+    /// that has not been sent yet. Fails with the error of a previous PUT,
+    /// if any.
+    pub fn send(&mut self, frame: Frame) -> Result<()> {
+        executor::block_on(self.inner.send(frame))
+    }
+
+    /// Reports the error of a previous PUT, if any.
+    pub fn flush(&mut self) -> Result<()> {
+        executor::block_on(self.inner.flush())
+    }
+
+    /// Closes the sink, blocking until the last frame was sent.
+    pub fn close(&mut self) -> Result<()> {
+        executor::block_on(self.inner.close())
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use lighthouse_protocol::{Authentication, Color, EventSource, Frame, InputEvent, KeyEvent, KeyModifiers, Model, Verb};
+
+    use crate::{LighthouseBuilder, MockLighthouse, RequestOptions};
+
+    #[test]
+    fn mirrors_async_client() {
+        let mock = MockLighthouse::new();
+        mock.add_user("alice");
+        let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+            .build_blocking(mock.connect())
+            .unwrap();
+        lh.put_model(Frame::fill(Color::RED)).unwrap();
+        lh.post("/a", 42).unwrap();
+        assert_eq!(lh.get::<i32>("/a").unwrap().payload, 42);
+        assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::RED)]);
+
+        let event = InputEvent::Key(KeyEvent {
+            source: EventSource::Int(0),
+            down: true,
+            repeat: false,
+            code: "KeyA".to_owned(),
+            modifiers: KeyModifiers::default(),
+        });
+        let mut input = lh.stream_input().unwrap();
+        mock.inject_input("alice", event.clone());
+        assert_eq!(input.next().unwrap().unwrap().payload, event);
+        drop(input);
+        while mock.requests().last().map(|request| &request.verb) != Some(&Verb::Stop) {
+            std::thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn mirrors_resources_and_frame_sinks() {
+        let mock = MockLighthouse::new();
+        mock.add_user("alice");
+        let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+            .build_blocking(mock.connect())
+            .unwrap();
+        let greeting = lh.resource::<String>("/greeting").unwrap();
+        greeting.post("Hello".to_owned()).unwrap();
+        assert_eq!(greeting.get().unwrap().payload, "Hello");
+
+        let mut updates = greeting.stream_with_gaps(&RequestOptions::new()).unwrap();
+        let update = updates.next().unwrap().unwrap().into_message().unwrap();
+        assert_eq!(update.payload, "Hello");
+        drop(updates);
+
+        lh.model().unwrap().put(Model::Frame(Frame::fill(Color::RED))).unwrap();
+        let mut frames = lh.frame_sink(None);
+        frames.send(Frame::fill(Color::GREEN)).unwrap();
+        frames.close().unwrap();
+        assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::RED), Frame::fill(Color::GREEN)]);
+    }
+}
@@ -0,0 +1,253 @@
+use std::{sync::Arc, time::Duration};
+
+use async_tungstenite::tungstenite::{self, Message};
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+use async_tungstenite::tungstenite::{client::IntoClientRequest, handshake::client::Request, http::{HeaderName, HeaderValue}};
+use futures::{Future, FutureExt, Sink, Stream};
+use lighthouse_protocol::Authentication;
+
+use crate::{lighthouse::{Config, Connector, Settings}, spawn_fn, EarlyMessageLimits, Error, Interceptor, Keepalive, Lighthouse, OverflowPolicy, ReconnectPolicy, RequestOptions, Result, RetryPolicy, SessionRecorder, SpawnFn, Spawner, LIGHTHOUSE_URL};
+
+/// A function creating the configuration for TLS connections.
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub(crate) type TlsConfig = Arc<dyn Fn() -> native_tls::TlsConnectorBuilder + Send + Sync>;
+
+/// A builder for configuring and establishing connections to the lighthouse.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use lighthouse_client::{LighthouseBuilder, protocol::Authentication};
+/// # async fn run() -> lighthouse_client::Result<()> {
+/// let lh = LighthouseBuilder::new(Authentication::new("user", "token"))
+///     .timeout(Duration::from_secs(5))
+///     .buffer(16)
+///     .header("User-Agent", "my-game")
+///     .connect_with_tokio()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct LighthouseBuilder {
+    url: String,
+    pub(crate) authentication: Authentication,
+    defaults: RequestOptions,
+    settings: Settings,
+    pub(crate) reconnect: Option<ReconnectPolicy>,
+    headers: Vec<(String, String)>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub(crate) tls: Option<TlsConfig>,
+    pub(crate) spawn: Option<SpawnFn>,
+}
+
+impl LighthouseBuilder {
+    /// Creates a new builder connecting to the default URL with the given
+    /// credentials and reconnecting with the default [`ReconnectPolicy`].
+    pub fn new(authentication: Authentication) -> Self {
+        Self {
+            url: LIGHTHOUSE_URL.to_owned(),
+            authentication,
+            defaults: RequestOptions::default(),
+            settings: Settings::default(),
+            reconnect: Some(ReconnectPolicy::default()),
+            headers: Vec::new(),
+            interceptors: Vec::new(),
+            #[cfg(any(feature = "tokio", feature = "async-std"))]
+            tls: None,
+            spawn: None,
+        }
+    }
+
+    /// Sets the URL of the lighthouse server.
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Sets the credentials used to authenticate with the lighthouse.
+    pub fn authentication(mut self, authentication: Authentication) -> Self {
+        self.authentication = authentication;
+        self
+    }
+
+    /// Sets the default options for requests. Options set on individual
+    /// requests take precedence.
+    pub fn request_options(mut self, options: RequestOptions) -> Self {
+        self.defaults = options;
+        self
+    }
+
+    /// Sets the default timeout for requests.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.defaults.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the default number of messages buffered per stream.
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.defaults.buffer = Some(buffer);
+        self
+    }
+
+    /// Sets the default policy to apply once a stream's buffer is full. If
+    /// unset, input streams never drop events and other streams drop the
+    /// oldest message, see [`OverflowPolicy`].
+    pub fn overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.defaults.overflow = Some(overflow);
+        self
+    }
+
+    /// Sets the default policy for retrying failed requests. Retrying is
+    /// disabled by default.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.defaults.retry = Some(policy);
+        self
+    }
+
+    /// Adds a META key/value sent along with every request, e.g. to identify
+    /// the client. META set on individual requests takes precedence.
+    pub fn meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defaults.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the keepalive configuration, `None` disables pings.
+    pub fn keepalive(mut self, keepalive: Option<Keepalive>) -> Self {
+        self.settings.keepalive = keepalive;
+        self
+    }
+
+    /// Sets the limits for messages received before their request was
+    /// registered.
+    pub fn early_message_limits(mut self, limits: EarlyMessageLimits) -> Self {
+        self.settings.early_message_limits = limits;
+        self
+    }
+
+    /// Records the messages exchanged over the connection with the given
+    /// recorder, see [`SessionRecorder`].
+    pub fn record(mut self, recorder: SessionRecorder) -> Self {
+        self.settings.recorder = Some(recorder);
+        self
+    }
+
+    /// Limits the number of requests awaiting their response. Further
+    /// requests wait until earlier ones are answered (or time out), so bulk
+    /// operations cannot flood the server. Requests sent without waiting
+    /// for the response count until they are answered or expire, see
+    /// [`Lighthouse::perform_nowait`](crate::Lighthouse::perform_nowait).
+    /// Streams do not count towards the limit. A limit of 0 is treated as 1.
+    /// There is no limit by default.
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.settings.max_in_flight = Some(max_in_flight.max(1));
+        self
+    }
+
+    /// Appends an interceptor to the chain observing and modifying the
+    /// messages exchanged over the connection, see [`Interceptor`].
+    pub fn interceptor(mut self, interceptor: impl Interceptor) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Sets the reconnect policy, `None` disables reconnecting.
+    pub fn reconnect(mut self, policy: Option<ReconnectPolicy>) -> Self {
+        self.reconnect = policy;
+        self
+    }
+
+    /// Adds a header to the HTTP request opening the WebSocket connection.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets a function configuring the TLS connector used for `wss://` URLs,
+    /// e.g. to trust additional root certificates. It is invoked for every
+    /// (re)connect.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub fn tls(mut self, configure: impl Fn() -> native_tls::TlsConnectorBuilder + Send + Sync + 'static) -> Self {
+        self.tls = Some(Arc::new(configure));
+        self
+    }
+
+    /// Sets the spawner used for background tasks. Defaults to the spawner
+    /// of the runtime connected with.
+    pub fn spawner<W>(mut self) -> Self where W: Spawner {
+        self.spawn = Some(spawn_fn::<W>());
+        self
+    }
+
+    /// Creates a connection from an already established WebSocket, which is
+    /// not reconnected, see [`LighthouseBuilder::build_with_connector`].
+    /// The URL, headers and TLS configuration are not used either, since
+    /// they only apply to connections established by the builder itself.
+    /// Since the runtime is unknown, this requires a spawner to be set,
+    /// unless exactly one runtime feature is enabled.
+    pub fn build<S>(self, web_socket: S) -> Result<Lighthouse<S>>
+    where
+        S: Stream<Item = tungstenite::Result<Message>>
+         + Sink<Message, Error = tungstenite::Error>
+         + Send
+         + 'static {
+        let spawn = self.spawn.or_else(default_spawn_fn).ok_or_else(|| Error::custom("No spawner configured"))?;
+        Lighthouse::with_config(web_socket, self.authentication.clone(), self.config(spawn))
+    }
+
+    /// Creates a connection from an already established WebSocket, which is
+    /// re-established via `connect` according to the reconnect policy
+    /// whenever it drops. Active streams are transparently resubscribed.
+    /// Like [`LighthouseBuilder::build`], this does not use the URL, headers
+    /// and TLS configuration and requires a spawner to be set, unless
+    /// exactly one runtime feature is enabled.
+    pub fn build_with_connector<S, C, F>(self, web_socket: S, connect: C) -> Result<Lighthouse<S>>
+    where
+        S: Stream<Item = tungstenite::Result<Message>>
+         + Sink<Message, Error = tungstenite::Error>
+         + Send
+         + 'static,
+        C: Fn() -> F + Send + Sync + 'static,
+        F: Future<Output = Result<S>> + Send + 'static {
+        let spawn = self.spawn.or_else(default_spawn_fn).ok_or_else(|| Error::custom("No spawner configured"))?;
+        let mut config = self.config(spawn);
+        config.reconnect = self.reconnect.clone().map(|policy| {
+            let connect: Connector<S> = Box::new(move || connect().boxed());
+            (policy, connect)
+        });
+        Lighthouse::with_config(web_socket, self.authentication, config)
+    }
+
+    /// Creates the configuration without a reconnect function.
+    pub(crate) fn config<S>(&self, spawn: SpawnFn) -> Config<S> {
+        Config {
+            defaults: self.defaults.clone(),
+            settings: self.settings.clone(),
+            reconnect: None,
+            interceptors: self.interceptors.clone(),
+            spawn,
+        }
+    }
+
+    /// Creates the HTTP request opening the WebSocket connection.
+    #[cfg(any(feature = "tokio", feature = "async-std"))]
+    pub(crate) fn request(&self) -> Result<Request> {
+        let mut request = self.url.as_str().into_client_request()?;
+        for (name, value) in &self.headers {
+            let name = HeaderName::try_from(name).map_err(|e| tungstenite::Error::HttpFormat(e.into()))?;
+            let value = HeaderValue::try_from(value).map_err(|e| tungstenite::Error::HttpFormat(e.into()))?;
+            request.headers_mut().append(name, value);
+        }
+        Ok(request)
+    }
+}
+
+/// Fetches the spawner of the only enabled runtime, if any.
+fn default_spawn_fn() -> Option<SpawnFn> {
+    #[cfg(all(feature = "tokio", not(feature = "async-std")))]
+    return Some(spawn_fn::<crate::TokioSpawner>());
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    return Some(spawn_fn::<crate::AsyncStdSpawner>());
+    #[allow(unreachable_code)]
+    None
+}
@@ -8,10 +8,10 @@ pub trait Check: Sized {
 
 impl<P> Check for ServerMessage<P> {
     fn check(self) -> Result<Self> {
-        if self.code >= 200 && self.code < 300 {
+        if self.code.is_success() {
             Ok(self)
         } else {
-            Err(Error::Server { code: self.code, message: self.response, warnings: self.warnings })
+            Err(Error::Server { code: self.code, message: self.response, warnings: self.warnings, attempts: 1 })
         }
     }
 }
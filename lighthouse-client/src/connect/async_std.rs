@@ -1,14 +1,19 @@
-use async_tungstenite::{WebSocketStream, async_std::{ConnectStream, connect_async}};
+use async_tungstenite::{WebSocketStream, async_std::{ConnectStream, connect_async_with_tls_connector}};
+use async_native_tls::TlsConnector;
+use futures::FutureExt;
+use lighthouse_protocol::Authentication;
 
-use crate::{Result, Lighthouse, Authentication, LIGHTHOUSE_URL, AsyncStdSpawner};
+use crate::{builder::TlsConfig, lighthouse::Connector, spawn_fn, Result, Lighthouse, LighthouseBuilder, LIGHTHOUSE_URL, AsyncStdSpawner};
 
 pub type AsyncStdWebSocket = WebSocketStream<ConnectStream>;
 
 impl Lighthouse<AsyncStdWebSocket> {
-    /// Connects to the lighthouse server at the given URL.
+    /// Connects to the lighthouse server at the given URL, automatically
+    /// reconnecting with the default [`ReconnectPolicy`](crate::ReconnectPolicy)
+    /// if the connection drops. Use a [`LighthouseBuilder`] to configure the
+    /// connection further.
     pub async fn connect_with_async_std_to(url: &str, authentication: Authentication) -> Result<Self> {
-        let (web_socket, _) = connect_async(url).await?;
-        Self::new::<AsyncStdSpawner>(web_socket, authentication)
+        LighthouseBuilder::new(authentication).url(url).connect_with_async_std().await
     }
 
     /// Connects to the lighthouse server at the default URL.
@@ -16,3 +21,27 @@ impl Lighthouse<AsyncStdWebSocket> {
         Self::connect_with_async_std_to(LIGHTHOUSE_URL, authentication).await
     }
 }
+
+impl LighthouseBuilder {
+    /// Connects to the lighthouse using async-std.
+    pub async fn connect_with_async_std(self) -> Result<Lighthouse<AsyncStdWebSocket>> {
+        let request = self.request()?;
+        let (web_socket, _) = connect_async_with_tls_connector(request.clone(), tls_connector(&self.tls)?).await?;
+        let mut config = self.config(self.spawn.unwrap_or_else(spawn_fn::<AsyncStdSpawner>));
+        config.reconnect = self.reconnect.clone().map(|policy| {
+            let tls = self.tls.clone();
+            let connect: Connector<AsyncStdWebSocket> = Box::new(move || {
+                let request = request.clone();
+                let connector = tls_connector(&tls);
+                async move { Ok(connect_async_with_tls_connector(request, connector?).await?.0) }.boxed()
+            });
+            (policy, connect)
+        });
+        Lighthouse::with_config(web_socket, self.authentication, config)
+    }
+}
+
+/// Creates the TLS connector from the given configuration, if any.
+fn tls_connector(tls: &Option<TlsConfig>) -> Result<Option<TlsConnector>> {
+    Ok(tls.as_ref().map(|configure| configure().into()))
+}
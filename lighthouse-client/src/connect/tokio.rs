@@ -1,15 +1,19 @@
-use async_tungstenite::{WebSocketStream, tokio::{ConnectStream, connect_async}};
+use async_tungstenite::{WebSocketStream, tokio::{ConnectStream, connect_async_with_tls_connector}, tungstenite::{self, error::TlsError}};
+use futures::FutureExt;
+use tokio_native_tls::TlsConnector;
 use lighthouse_protocol::Authentication;
 
-use crate::{Result, Lighthouse, LIGHTHOUSE_URL, TokioSpawner};
+use crate::{builder::TlsConfig, lighthouse::Connector, spawn_fn, Result, Lighthouse, LighthouseBuilder, LIGHTHOUSE_URL, TokioSpawner};
 
 pub type TokioWebSocket = WebSocketStream<ConnectStream>;
 
 impl Lighthouse<TokioWebSocket> {
-    /// Connects to the lighthouse server at the given URL.
+    /// Connects to the lighthouse server at the given URL, automatically
+    /// reconnecting with the default [`ReconnectPolicy`](crate::ReconnectPolicy)
+    /// if the connection drops. Use a [`LighthouseBuilder`] to configure the
+    /// connection further.
     pub async fn connect_with_tokio_to(url: &str, authentication: Authentication) -> Result<Self> {
-        let (web_socket, _) = connect_async(url).await?;
-        Self::new::<TokioSpawner>(web_socket, authentication)
+        LighthouseBuilder::new(authentication).url(url).connect_with_tokio().await
     }
 
     /// Connects to the lighthouse server at the default URL.
@@ -17,3 +21,29 @@ impl Lighthouse<TokioWebSocket> {
         Self::connect_with_tokio_to(LIGHTHOUSE_URL, authentication).await
     }
 }
+
+impl LighthouseBuilder {
+    /// Connects to the lighthouse using tokio.
+    pub async fn connect_with_tokio(self) -> Result<Lighthouse<TokioWebSocket>> {
+        let request = self.request()?;
+        let (web_socket, _) = connect_async_with_tls_connector(request.clone(), tls_connector(&self.tls)?).await?;
+        let mut config = self.config(self.spawn.unwrap_or_else(spawn_fn::<TokioSpawner>));
+        config.reconnect = self.reconnect.clone().map(|policy| {
+            let tls = self.tls.clone();
+            let connect: Connector<TokioWebSocket> = Box::new(move || {
+                let request = request.clone();
+                let connector = tls_connector(&tls);
+                async move { Ok(connect_async_with_tls_connector(request, connector?).await?.0) }.boxed()
+            });
+            (policy, connect)
+        });
+        Lighthouse::with_config(web_socket, self.authentication, config)
+    }
+}
+
+/// Creates the TLS connector from the given configuration, if any.
+fn tls_connector(tls: &Option<TlsConfig>) -> Result<Option<TlsConnector>> {
+    let Some(configure) = tls else { return Ok(None) };
+    let connector = configure().build().map_err(|e| tungstenite::Error::Tls(TlsError::Native(e)))?;
+    Ok(Some(connector.into()))
+}
@@ -1,5 +1,7 @@
+use std::{convert::Infallible, fmt};
+
 use async_tungstenite::tungstenite;
-use lighthouse_protocol::ValueError;
+use lighthouse_protocol::{PathError, ResourcePath, StatusCode, ValueError, Verb};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -8,24 +10,102 @@ pub type Result<T> = std::result::Result<T, Error>;
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("Tungstenite (WebSocket) error: {0}")]
-    Tungstenite(#[from] tungstenite::Error),
+    Tungstenite(#[from] Box<tungstenite::Error>),
     #[error("MessagePack encoding error: {0}")]
     Encode(#[from] rmp_serde::encode::Error),
     #[error("MessagePack decoding error: {0}")]
     Decode(#[from] rmp_serde::decode::Error),
     #[error("MessagePack value error: {0}")]
     Value(#[from] ValueError),
-    #[error("Server error: {} {} (warnings: {:?})", code, message.clone().unwrap_or_else(|| "(no message)".to_string()), warnings)]
-    Server { code: i32, message: Option<String>, warnings: Vec<String> },
+    #[error("Invalid resource path: {0}")]
+    Path(#[from] PathError),
+    #[error("Server error: {} {} (warnings: {:?}, attempts: {})", code, message.clone().unwrap_or_else(|| "(no message)".to_string()), warnings, attempts)]
+    Server { code: StatusCode, message: Option<String>, warnings: Vec<String>, attempts: u32 },
     #[error("No next message available")]
     NoNextMessage,
     #[error("The connection was closed")]
     ConnectionClosed,
-    #[error("Custom error")]
+    #[error("The request timed out")]
+    Timeout,
+    #[error("The stream's buffer overflowed")]
+    BufferOverflow,
+    #[error("{0}")]
     Custom(String),
+    #[error("{context}")]
+    Request { context: RequestContext, #[source] source: Box<Error> },
+}
+
+impl From<tungstenite::Error> for Error {
+    fn from(error: tungstenite::Error) -> Self {
+        Self::Tungstenite(Box::new(error))
+    }
+}
+
+impl From<Infallible> for Error {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// The request during which an error occurred, see [`Error::Request`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestContext {
+    /// The verb of the request.
+    pub verb: Verb,
+    /// The path of the requested resource.
+    pub path: ResourcePath,
+    /// The id of the request.
+    pub request_id: i32,
+}
+
+impl RequestContext {
+    /// Creates the context of the given request.
+    pub fn new(verb: &Verb, path: &ResourcePath, request_id: i32) -> Self {
+        Self { verb: verb.clone(), path: path.clone(), request_id }
+    }
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (request id {})", self.verb, self.path, self.request_id)
+    }
 }
 
 impl Error {
     /// Creates a new `LighthouseError` from the given custom message.
     pub fn custom(s: &str) -> Self { Self::Custom(s.to_owned()) }
+
+    /// Attaches the context of the failed request, unless the error already
+    /// carries one.
+    pub(crate) fn with_context(self, context: RequestContext) -> Self {
+        match self {
+            Self::Request { .. } => self,
+            error => Self::Request { context, source: Box::new(error) },
+        }
+    }
+
+    /// Fetches the context of the failed request, if known.
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            Self::Request { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// Fetches the underlying error, i.e. strips the request context. Useful
+    /// for matching, e.g. on [`Error::Server`].
+    pub fn without_context(&self) -> &Error {
+        match self {
+            Self::Request { source, .. } => source.without_context(),
+            error => error,
+        }
+    }
+
+    /// Fetches the status code, if this is an error response from the server.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self.without_context() {
+            Self::Server { code, .. } => Some(*code),
+            _ => None,
+        }
+    }
 }
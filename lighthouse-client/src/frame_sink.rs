@@ -0,0 +1,153 @@
+use std::{pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll, Waker}, time::{Duration, Instant}};
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{future, Sink, Stream};
+use futures_timer::Delay;
+use lighthouse_protocol::Frame;
+use tracing::trace;
+
+use crate::{Error, Lighthouse, Result};
+
+/// A sink that sends frames to the user's lighthouse model, favoring the
+/// newest frame over delivering every frame.
+///
+/// At most one PUT is in flight at any time. Frames sent while a PUT is in
+/// flight replace each other, so only the newest one is sent next. This way
+/// a slow connection results in dropped frames rather than in growing
+/// latency. Optionally, the rate at which frames are sent can be capped.
+///
+/// Flushing only hands the newest frame to the background sender and does
+/// not wait for the server's acknowledgement, closing the sink waits for the
+/// last frame to be sent. Errors from the server are reported by the next
+/// operation on the sink.
+pub struct FrameSink {
+    shared: Arc<Mutex<Shared>>,
+}
+
+/// The state shared between a [`FrameSink`] and its background sender.
+#[derive(Default)]
+struct Shared {
+    /// The newest frame that has not been sent yet.
+    pending: Option<Frame>,
+    /// Whether a PUT is currently in flight.
+    in_flight: bool,
+    /// Whether the sink was closed or dropped.
+    closed: bool,
+    /// An error that has not been reported yet.
+    error: Option<Error>,
+    /// The waker of the background sender waiting for a frame.
+    sender_waker: Option<Waker>,
+    /// The waker of a task waiting for the sink to close.
+    close_waker: Option<Waker>,
+}
+
+impl FrameSink {
+    /// Creates a new `FrameSink` sending frames via the given connection,
+    /// optionally capping the rate at the given number of frames per second.
+    /// Rates that are not positive (or not a number) leave it uncapped.
+    pub(crate) fn new<S>(lh: Lighthouse<S>, max_fps: Option<f64>) -> Self
+    where
+        S: Stream<Item = tungstenite::Result<Message>>
+         + futures::Sink<Message, Error = tungstenite::Error>
+         + Send
+         + 'static {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let min_interval = max_fps.and_then(|fps| Duration::try_from_secs_f64(fps.recip()).ok());
+        lh.spawn(Self::run_sender(lh.clone(), shared.clone(), min_interval));
+        Self { shared }
+    }
+
+    /// Runs a loop that continuously sends the newest pending frame.
+    async fn run_sender<S>(lh: Lighthouse<S>, shared: Arc<Mutex<Shared>>, min_interval: Option<Duration>)
+    where
+        S: Stream<Item = tungstenite::Result<Message>>
+         + futures::Sink<Message, Error = tungstenite::Error>
+         + Send
+         + 'static {
+        loop {
+            let frame = future::poll_fn(|cx| {
+                let mut shared = shared.lock().unwrap();
+                if let Some(frame) = shared.pending.take() {
+                    shared.in_flight = true;
+                    Poll::Ready(Some(frame))
+                } else if shared.closed {
+                    Poll::Ready(None)
+                } else {
+                    shared.sender_waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }).await;
+            let Some(frame) = frame else { break };
+
+            let started = Instant::now();
+            let result = lh.put_model(frame).await;
+            {
+                let mut shared = shared.lock().unwrap();
+                shared.in_flight = false;
+                if let Err(error) = result {
+                    shared.error = Some(error);
+                }
+                if let Some(waker) = shared.close_waker.take() {
+                    waker.wake();
+                }
+            }
+
+            if let Some(remaining) = min_interval.and_then(|interval| interval.checked_sub(started.elapsed())) {
+                Delay::new(remaining).await;
+            }
+        }
+    }
+
+    /// Reports the pending error, if any.
+    fn take_error(shared: &mut Shared) -> Result<()> {
+        shared.error.take().map_or(Ok(()), Err)
+    }
+}
+
+impl Sink<Frame> for FrameSink {
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Self::take_error(&mut self.shared.lock().unwrap()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: Frame) -> Result<()> {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.pending.replace(frame).is_some() {
+            trace!("Dropping superseded frame");
+        }
+        if let Some(waker) = shared.sender_waker.take() {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Self::take_error(&mut self.shared.lock().unwrap()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        if let Some(waker) = shared.sender_waker.take() {
+            waker.wake();
+        }
+        if shared.pending.is_none() && !shared.in_flight {
+            Poll::Ready(Self::take_error(&mut shared))
+        } else {
+            shared.close_waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for FrameSink {
+    fn drop(&mut self) {
+        // Let the background sender finish the pending frame and exit
+        let mut shared = self.shared.lock().unwrap();
+        shared.closed = true;
+        if let Some(waker) = shared.sender_waker.take() {
+            waker.wake();
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use std::{collections::VecDeque, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll, Waker}};
+
+use futures::Stream;
+use lighthouse_protocol::Value;
+use tracing::debug;
+
+use crate::{Error, OverflowPolicy, Result, StreamEvent};
+
+/// Creates a new inbox, i.e. a channel whose sending end never waits for the
+/// receiving end and instead applies the given overflow policy once more
+/// than `capacity` events are buffered.
+pub(crate) fn inbox(capacity: usize, overflow: OverflowPolicy) -> (Inbox, InboxReceiver) {
+    let shared = Arc::new(Mutex::new(Shared {
+        queue: VecDeque::new(),
+        capacity,
+        overflow,
+        error: None,
+        closed: false,
+        receiver_dropped: false,
+        waker: None,
+    }));
+    (Inbox { shared: shared.clone() }, InboxReceiver { shared })
+}
+
+/// The state shared between an [`Inbox`] and its [`InboxReceiver`].
+struct Shared {
+    /// The buffered events.
+    queue: VecDeque<StreamEvent<Value>>,
+    /// The number of messages to buffer before applying the overflow policy.
+    capacity: usize,
+    /// The policy to apply once the buffer is full.
+    overflow: OverflowPolicy,
+    /// The error to yield after the buffered events.
+    error: Option<Error>,
+    /// Whether no more events will be pushed.
+    closed: bool,
+    /// Whether the receiver has been dropped.
+    receiver_dropped: bool,
+    /// The waker of the task waiting for events.
+    waker: Option<Waker>,
+}
+
+impl Shared {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// The sending end of an inbox, owned by a slot.
+pub(crate) struct Inbox {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Inbox {
+    /// Pushes the given event without ever waiting. Returns `false` if the
+    /// inbox will not accept any further events, i.e. the slot can be
+    /// removed.
+    pub(crate) fn push(&self, event: StreamEvent<Value>) -> bool {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.receiver_dropped || shared.closed {
+            return false;
+        }
+        // Gaps are markers rather than messages and therefore always delivered
+        if matches!(event, StreamEvent::Message(_)) && shared.queue.len() >= shared.capacity {
+            match shared.overflow {
+                OverflowPolicy::Unbounded => {},
+                OverflowPolicy::DropOldest => {
+                    debug!("Buffer full, dropping oldest message");
+                    let oldest = shared.queue.iter().position(|event| matches!(event, StreamEvent::Message(_)));
+                    if let Some(index) = oldest {
+                        shared.queue.remove(index);
+                    }
+                },
+                OverflowPolicy::DropNewest => {
+                    debug!("Buffer full, dropping newest message");
+                    return true;
+                },
+                OverflowPolicy::Error => {
+                    debug!("Buffer full, failing subscription");
+                    shared.error = Some(Error::BufferOverflow);
+                    shared.closed = true;
+                    shared.wake();
+                    return false;
+                },
+            }
+        }
+        shared.queue.push_back(event);
+        shared.wake();
+        true
+    }
+
+    /// Whether the receiver has been dropped, i.e. the slot owning this
+    /// inbox can be removed.
+    pub(crate) fn is_receiver_dropped(&self) -> bool {
+        self.shared.lock().unwrap().receiver_dropped
+    }
+
+    /// Closes the inbox, yielding the given error after the buffered events.
+    pub(crate) fn close(&self, error: Option<Error>) {
+        let mut shared = self.shared.lock().unwrap();
+        if !shared.closed {
+            shared.closed = true;
+            shared.error = error;
+            shared.wake();
+        }
+    }
+}
+
+impl Drop for Inbox {
+    fn drop(&mut self) {
+        self.close(None);
+    }
+}
+
+/// The receiving end of an inbox.
+pub(crate) struct InboxReceiver {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl Stream for InboxReceiver {
+    type Item = Result<StreamEvent<Value>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(event) = shared.queue.pop_front() {
+            Poll::Ready(Some(Ok(event)))
+        } else if shared.closed {
+            Poll::Ready(shared.error.take().map(Err))
+        } else {
+            shared.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for InboxReceiver {
+    fn drop(&mut self) {
+        let mut shared = self.shared.lock().unwrap();
+        shared.receiver_dropped = true;
+        shared.queue.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use futures::{executor::block_on, StreamExt};
+    use lighthouse_protocol::{ServerMessage, StatusCode, Value};
+
+    use crate::{Error, OverflowPolicy, StreamEvent};
+
+    use super::{inbox, InboxReceiver};
+
+    fn message(i: i32) -> StreamEvent<Value> {
+        StreamEvent::Message(ServerMessage { code: StatusCode::Ok, request_id: Some(0), warnings: Vec::new(), response: None, meta: HashMap::new(), payload: Value::from(i) })
+    }
+
+    fn payloads(rx: InboxReceiver) -> Vec<Option<i64>> {
+        block_on(rx.map(|event| match event {
+            Ok(StreamEvent::Message(message)) => message.payload.as_i64(),
+            Ok(StreamEvent::Gap) | Err(_) => None,
+        }).collect())
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let (tx, rx) = inbox(2, OverflowPolicy::DropOldest);
+        for i in 0..4 {
+            assert!(tx.push(message(i)));
+        }
+        drop(tx);
+        assert_eq!(payloads(rx), vec![Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let (tx, rx) = inbox(2, OverflowPolicy::DropNewest);
+        for i in 0..4 {
+            assert!(tx.push(message(i)));
+        }
+        assert!(tx.push(StreamEvent::Gap));
+        drop(tx);
+        assert_eq!(payloads(rx), vec![Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn unbounded_keeps_everything() {
+        let (tx, rx) = inbox(1, OverflowPolicy::Unbounded);
+        for i in 0..3 {
+            assert!(tx.push(message(i)));
+        }
+        drop(tx);
+        assert_eq!(payloads(rx), vec![Some(0), Some(1), Some(2)]);
+    }
+
+    #[test]
+    fn error_fails_stream() {
+        let (tx, mut rx) = inbox(1, OverflowPolicy::Error);
+        assert!(tx.push(message(0)));
+        assert!(!tx.push(message(1)));
+        assert!(matches!(block_on(rx.next()), Some(Ok(StreamEvent::Message(_)))));
+        assert!(matches!(block_on(rx.next()), Some(Err(Error::BufferOverflow))));
+        assert!(block_on(rx.next()).is_none());
+    }
+
+    #[test]
+    fn dropped_receiver() {
+        let (tx, rx) = inbox(1, OverflowPolicy::Unbounded);
+        drop(rx);
+        assert!(!tx.push(message(0)));
+    }
+}
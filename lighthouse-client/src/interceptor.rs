@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use lighthouse_protocol::{ClientMessage, ServerMessage, Value};
+
+/// A hook observing and modifying the messages exchanged over a connection,
+/// e.g. to log full payloads, inject META, rewrite paths or inject faults in
+/// tests. Interceptors are added via
+/// [`LighthouseBuilder::interceptor`](crate::LighthouseBuilder::interceptor).
+///
+/// Interceptors form a chain: requests pass through them in the order they
+/// were added, responses (and other incoming messages) in reverse order.
+/// Both methods do nothing by default.
+///
+/// ```
+/// # use lighthouse_client::{Interceptor, RequestAction, protocol::{ClientMessage, Value}};
+/// /// Prefixes all paths with the tenant's directory.
+/// struct Tenant(String);
+///
+/// impl Interceptor for Tenant {
+///     fn on_request(&self, request: &mut ClientMessage<Value>) -> RequestAction {
+///         request.path.insert(0, self.0.clone());
+///         RequestAction::Continue
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send + Sync + 'static {
+    /// Intercepts a request (including STOPs and resubscriptions) before it
+    /// is sent. The request carries the credentials, which should not be
+    /// logged.
+    fn on_request(&self, request: &mut ClientMessage<Value>) -> RequestAction {
+        let _ = request;
+        RequestAction::Continue
+    }
+
+    /// Intercepts a message received from the server (or produced by a
+    /// later interceptor via [`RequestAction::Respond`]) before it is
+    /// delivered.
+    fn on_response(&self, response: &mut ServerMessage<Value>) -> ResponseAction {
+        let _ = response;
+        ResponseAction::Continue
+    }
+}
+
+/// What to do with an intercepted request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestAction {
+    /// Passes the request on to the next interceptor and eventually sends it.
+    Continue,
+    /// Does not send the request and answers it with the given response
+    /// instead, which passes back through the preceding interceptors. The
+    /// request id of the response defaults to the one of the request.
+    Respond(ServerMessage<Value>),
+}
+
+/// What to do with an intercepted incoming message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseAction {
+    /// Passes the message on to the next interceptor and eventually
+    /// delivers it.
+    Continue,
+    /// Drops the message.
+    Discard,
+}
+
+/// Passes the given request through the given interceptors. Returns the
+/// index of the interceptor short-circuiting the request along with its
+/// response, if any.
+pub(crate) fn intercept_request(interceptors: &[Arc<dyn Interceptor>], request: &mut ClientMessage<Value>) -> Option<(usize, ServerMessage<Value>)> {
+    interceptors.iter().enumerate().find_map(|(index, interceptor)| match interceptor.on_request(request) {
+        RequestAction::Continue => None,
+        RequestAction::Respond(response) => Some((index, response)),
+    })
+}
+
+/// Passes the given incoming message through the given interceptors in
+/// reverse order. Returns whether it should be delivered.
+pub(crate) fn intercept_response(interceptors: &[Arc<dyn Interceptor>], response: &mut ServerMessage<Value>) -> bool {
+    interceptors.iter().rev().all(|interceptor| interceptor.on_response(response) == ResponseAction::Continue)
+}
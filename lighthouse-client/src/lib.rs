@@ -1,23 +1,56 @@
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod batch;
+mod builder;
 mod check;
 mod connect;
 mod constants;
 mod error;
+mod frame_sink;
+mod inbox;
+mod interceptor;
 mod lighthouse;
+mod limiter;
+#[cfg(feature = "mock")]
+mod mock;
+mod options;
+mod reconnect;
+mod resource;
+mod retry;
+mod session;
 mod spawn;
+mod state;
+mod stats;
+mod subscribers;
 
+pub use batch::*;
+pub use builder::*;
 pub use check::*;
 pub use connect::*;
 pub use constants::*;
 pub use error::*;
+pub use frame_sink::*;
+pub use interceptor::*;
 pub use lighthouse::*;
+#[cfg(feature = "mock")]
+pub use mock::*;
+pub use options::*;
+pub use reconnect::*;
+pub use resource::*;
+pub use retry::*;
+pub use session::*;
 pub use spawn::*;
+pub use state::*;
+pub use stats::*;
 
 pub use lighthouse_protocol as protocol;
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use native_tls;
 
 /// Small convenience macro that expresses the root path.
 #[macro_export]
 macro_rules! root {
     () => {
-        &[] as &[&str]
+        $crate::protocol::ResourcePath::root()
     };
 }
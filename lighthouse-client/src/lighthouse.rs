@@ -1,39 +1,185 @@
-use std::{collections::HashMap, fmt::Debug, sync::{atomic::{AtomicI32, Ordering}, Arc}};
+use std::{collections::HashMap, fmt::Debug, mem, pin::pin, sync::{atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering}, Arc}, time::{Duration, Instant}};
 
 use async_tungstenite::tungstenite::{Message, self};
-use futures::{prelude::*, channel::mpsc::{Sender, self}, stream::{SplitSink, SplitStream}, lock::Mutex};
-use lighthouse_protocol::{Authentication, ClientMessage, DirectoryTree, Frame, InputEvent, LaserMetrics, Model, ServerMessage, Value, Verb};
+use futures::{prelude::*, future::{BoxFuture, Either}, stream::{SplitSink, SplitStream}, lock::Mutex};
+use futures_timer::Delay;
+use lighthouse_protocol::{path, to_value, Authentication, ClientMessage, DirectoryTree, Frame, InputEvent, LaserMetrics, Model, ResourcePath, ServerMessage, Value, Verb};
 use serde::{Deserialize, Serialize};
 use stream_guard::GuardStreamExt;
-use tracing::{warn, error, debug, info};
-use crate::{Check, Error, Result, Spawner};
+use tracing::{warn, error, debug, info, trace};
+use crate::{inbox::{inbox, Inbox, InboxReceiver}, interceptor::{intercept_request, intercept_response}, limiter::{Limiter, Permit}, spawn_fn, spawn_with, subscribers::{Subscribers, Watch}, Batch, Check, ConnectionState, EarlyMessageLimits, Error, FrameSink, Interceptor, Keepalive, OverflowPolicy, Resource, ReconnectPolicy, RequestContext, RequestOptions, Result, SessionRecorder, SpawnFn, Spawner, Stats, StreamEvent, Telemetry};
+
+/// The number of messages buffered for a stream by default.
+pub(crate) const DEFAULT_BUFFER: usize = 4;
+
+/// A function establishing a fresh WebSocket connection.
+pub(crate) type Connector<S> = Box<dyn Fn() -> BoxFuture<'static, Result<S>> + Send + Sync>;
+
+/// Converts the given path, e.g. a `&str`, into a [`ResourcePath`], failing
+/// with [`Error::Path`] if it is invalid.
+pub(crate) fn into_path(path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ResourcePath> {
+    path.try_into().map_err(Into::into)
+}
 
 /// A connection to the lighthouse server for sending requests and receiving events.
 pub struct Lighthouse<S> {
     /// The sink-part of the WebSocket connection.
     ws_sink: Arc<Mutex<SplitSink<S, Message>>>,
     /// The response/event slots, keyed by request id.
-    slots: Arc<Mutex<HashMap<i32, Slot<ServerMessage<Value>>>>>,
+    slots: Arc<Mutex<HashMap<i32, Slot>>>,
+    /// The STREAM requests that are currently active, keyed by request id.
+    /// These are re-sent after reconnecting and shared by all subscribers
+    /// streaming the same path with the same payload and META.
+    streams: Arc<Mutex<HashMap<i32, ClientMessage<Value>>>>,
     /// The credentials used to authenticate with the lighthouse.
     authentication: Authentication,
+    /// The options used for requests that do not override them.
+    defaults: RequestOptions,
     /// The next request id. Incremented on every request.
     request_id: Arc<AtomicI32>,
+    /// The next stream subscriber id. Incremented on every subscription.
+    subscriber_id: Arc<AtomicUsize>,
+    /// Whether the connection was closed deliberately via [`Lighthouse::close`].
+    closed: Arc<AtomicBool>,
+    /// The settings applying to the entire connection.
+    settings: Arc<std::sync::Mutex<Settings>>,
+    /// The state of the keepalive pings.
+    ping: Arc<std::sync::Mutex<PingState>>,
+    /// The subscribers to failed responses to unacknowledged requests.
+    unacknowledged_errors: Arc<Subscribers<(RequestContext, ServerMessage<()>)>>,
+    /// The subscribers to successful responses carrying warnings.
+    warnings: Arc<Subscribers<ServerMessage<()>>>,
+    /// The subscribers to messages pushed by the server without a request id.
+    notifications: Arc<Subscribers<ServerMessage<Value>>>,
+    /// The current state of the connection, observable via [`Lighthouse::state`].
+    state: Arc<Watch<ConnectionState>>,
+    /// The statistics of the connection, see [`Lighthouse::stats`].
+    telemetry: Arc<Telemetry>,
+    /// The limiter for requests awaiting their response.
+    limiter: Arc<Limiter>,
+    /// The interceptors for outgoing and incoming messages.
+    interceptors: Arc<[Arc<dyn Interceptor>]>,
+    /// The spawner used for background tasks.
+    spawn: SpawnFn,
+    /// Whether the receive loop has terminated. Only accessed while holding
+    /// the lock on the slots, so no new slots are registered afterwards.
+    terminated: Arc<AtomicBool>,
+}
+
+/// Settings applying to the entire connection, including the receive loop.
+#[derive(Debug, Clone)]
+pub(crate) struct Settings {
+    /// The timeout for requests that do not override it, `None` waiting
+    /// indefinitely.
+    pub(crate) timeout: Option<Duration>,
+    /// The limits for messages received before their slot was registered.
+    pub(crate) early_message_limits: EarlyMessageLimits,
+    /// The keepalive configuration, `None` disables pings.
+    pub(crate) keepalive: Option<Keepalive>,
+    /// The recorder for the exchanged messages, if any.
+    pub(crate) recorder: Option<SessionRecorder>,
+    /// The maximum number of requests awaiting their response, `None`
+    /// meaning unlimited.
+    pub(crate) max_in_flight: Option<usize>,
+}
+
+/// The configuration a connection is created with.
+pub(crate) struct Config<S> {
+    /// The options used for requests that do not override them.
+    pub(crate) defaults: RequestOptions,
+    /// The settings applying to the entire connection.
+    pub(crate) settings: Settings,
+    /// The reconnect policy and the function establishing fresh connections,
+    /// `None` disables reconnecting.
+    pub(crate) reconnect: Option<(ReconnectPolicy, Connector<S>)>,
+    /// The interceptors for outgoing and incoming messages.
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    /// The spawner used for background tasks.
+    pub(crate) spawn: SpawnFn,
+}
+
+impl<S> Config<S> {
+    /// Creates a default configuration using the given spawner.
+    pub(crate) fn new<W>() -> Self where W: Spawner {
+        Self {
+            defaults: RequestOptions::default(),
+            settings: Settings::default(),
+            reconnect: None,
+            interceptors: Vec::new(),
+            spawn: spawn_fn::<W>(),
+        }
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            early_message_limits: EarlyMessageLimits::default(),
+            keepalive: Some(Keepalive::default()),
+            recorder: None,
+            max_in_flight: None,
+        }
+    }
+}
+
+/// The state of the keepalive pings for the current connection.
+#[derive(Debug, Default)]
+struct PingState {
+    /// The time at which the last ping was sent.
+    sent: Option<Instant>,
+    /// Whether the last ping has not been answered yet.
+    awaiting_pong: bool,
+    /// The most recently measured round-trip latency.
+    latency: Option<Duration>,
 }
 
 /// A facility for coordinating asynchronous responses to a request between a
 /// requesting task and a receive loop task.
-enum Slot<M> {
+enum Slot {
     /// Indicates that messages were received before the requesting task
     /// registered the slot. **The receive loop** will construct this variant in
     /// that case, i.e. store the already received messages in a
-    /// [`Slot::EarlyMessages`].
-    EarlyMessages(Vec<M>),
+    /// [`Slot::EarlyMessages`]. These are subject to the
+    /// [`EarlyMessageLimits`].
+    EarlyMessages {
+        /// The received messages.
+        messages: Vec<ServerMessage<Value>>,
+        /// The time at which the first message was received.
+        since: Instant,
+    },
     /// Indicates that no messages were received before the requesting task
     /// registered the slot. **The requesting thread** will construct this
-    /// variant in that case, i.e. create a channel, store the sender in a
+    /// variant in that case, i.e. create an inbox, store it in a
     /// [`Slot::WaitForMessages`] for the receive loop and then return the
-    /// receiver.
-    WaitForMessages(Sender<M>),
+    /// receiver. Since pushing to an inbox never waits, a slow consumer
+    /// cannot hold up the receive loop.
+    WaitForMessages(Inbox),
+    /// Indicates an active STREAM request, whose messages are fanned out to
+    /// all local subscribers. **The requesting thread** will construct this
+    /// variant before sending the request, further subscribers to the same
+    /// stream add their inboxes to it and the last subscriber to leave
+    /// removes it.
+    Streaming {
+        /// The inboxes of the subscribers, keyed by subscriber id.
+        inboxes: HashMap<usize, Inbox>,
+        /// The most recent message, which is replayed to new subscribers
+        /// in place of the server's initial response.
+        last: Option<ServerMessage<Value>>,
+    },
+    /// Indicates that the requesting task does not wait for the response.
+    /// **The requesting thread** will construct this variant before sending
+    /// an unacknowledged request, **the receive loop** will remove it once
+    /// the response arrives and report it if it is an error, or once it
+    /// expires without a response (see [`Lighthouse::perform_nowait`]).
+    Detached {
+        /// The request, for reporting errors.
+        context: RequestContext,
+        /// The time at which the request was sent.
+        sent: Instant,
+        /// The permit for the request, returned once the slot is removed.
+        _permit: Permit,
+    },
 }
 
 impl<S> Lighthouse<S>
@@ -44,98 +190,453 @@ impl<S> Lighthouse<S>
     /// Connects to the lighthouse using the given credentials.
     /// Asynchronously runs a receive loop using the provided spawner.
     pub fn new<W>(web_socket: S, authentication: Authentication) -> Result<Self> where W: Spawner {
+        Self::with_config(web_socket, authentication, Config::new::<W>())
+    }
+
+    /// Connects to the lighthouse using the given credentials and
+    /// configuration. Asynchronously runs a receive loop using the configured
+    /// spawner.
+    pub(crate) fn with_config(web_socket: S, authentication: Authentication, config: Config<S>) -> Result<Self> {
+        let Config { mut defaults, mut settings, reconnect, interceptors, spawn } = config;
+        // The default timeout is shared by all handles, so it can be changed
+        if let Some(timeout) = defaults.timeout.take() {
+            settings.timeout = Some(timeout);
+        }
         let (ws_sink, ws_stream) = web_socket.split();
-        let slots = Arc::new(Mutex::new(HashMap::new()));
         let lh = Self {
             ws_sink: Arc::new(Mutex::new(ws_sink)),
-            slots: slots.clone(),
+            slots: Arc::new(Mutex::new(HashMap::new())),
+            streams: Arc::new(Mutex::new(HashMap::new())),
             authentication,
+            defaults,
             request_id: Arc::new(AtomicI32::new(0)),
+            subscriber_id: Arc::new(AtomicUsize::new(0)),
+            closed: Arc::new(AtomicBool::new(false)),
+            settings: Arc::new(std::sync::Mutex::new(settings)),
+            ping: Arc::new(std::sync::Mutex::new(PingState::default())),
+            unacknowledged_errors: Arc::new(Subscribers::default()),
+            warnings: Arc::new(Subscribers::default()),
+            notifications: Arc::new(Subscribers::default()),
+            state: Arc::new(Watch::new(ConnectionState::Connected)),
+            telemetry: Arc::new(Telemetry::default()),
+            limiter: Arc::new(Limiter::default()),
+            interceptors: interceptors.into(),
+            spawn,
+            terminated: Arc::new(AtomicBool::new(false)),
         };
-        W::spawn(Self::run_receive_loop(ws_stream, slots));
+        lh.spawn(lh.clone().run_receive_loop(ws_stream, reconnect));
         Ok(lh)
     }
 
-    /// Runs a loop that continuously receives events.
-    #[tracing::instrument(skip(ws_stream, slots))]
-    async fn run_receive_loop(mut ws_stream: SplitStream<S>, slots: Arc<Mutex<HashMap<i32, Slot<ServerMessage<Value>>>>>) {
+    /// Runs a loop that continuously receives events, reconnecting if
+    /// configured to do so.
+    #[tracing::instrument(skip_all)]
+    async fn run_receive_loop(self, mut ws_stream: SplitStream<S>, reconnect: Option<(ReconnectPolicy, Connector<S>)>) {
         loop {
-            match Self::receive_message_from(&mut ws_stream).await {
-                Ok(msg) => {
-                    let mut slots = slots.lock().await;
-                    if let Some(request_id) = msg.request_id {
-                        if let Some(slot) = slots.get_mut(&request_id) {
-                            match slot {
-                                Slot::EarlyMessages(msgs) => msgs.push(msg),
-                                Slot::WaitForMessages(tx) => {
-                                    if let Err(e) = tx.send(msg).await {
-                                        if e.is_disconnected() {
-                                            info!("Receiver for request id {} disconnected, removing the sender...", request_id);
-                                            slots.remove(&request_id);
-                                        } else {
-                                            warn!("Could not send message for request id {} via channel: {:?}", request_id, e);
-                                        }
-                                    }
-                                }
-                            }
-                        } else {
-                            slots.insert(request_id, Slot::EarlyMessages(vec![msg]));
-                        }
-                    } else {
-                        warn!("Got message without request id from server: {:?}", msg);
+            let reason = self.dispatch_messages(&mut ws_stream).await;
+            if self.closed.load(Ordering::Relaxed) {
+                break;
+            }
+            self.set_state(ConnectionState::Disconnected { reason });
+            let Some((policy, connect)) = &reconnect else { break };
+            match self.reconnect(policy, connect).await {
+                Some(new_ws_stream) => ws_stream = new_ws_stream,
+                None => break,
+            }
+        }
+
+        // Fail all pending requests and streams, since nothing will arrive anymore
+        self.streams.lock().await.clear();
+        self.telemetry.set_streams(0);
+        let mut slots = self.slots.lock().await;
+        self.terminated.store(true, Ordering::Relaxed);
+        Self::fail_slots(&mut slots, |_| false);
+        self.set_state(ConnectionState::Closed);
+    }
+
+    /// Transitions to the given connection state, notifying observers. Once
+    /// closed, the state does not change anymore.
+    fn set_state(&self, state: ConnectionState) {
+        let description = state.to_string();
+        let closed = state == ConnectionState::Closed;
+        if self.state.set(state, closed) {
+            debug! { state = %description, "Connection state changed" };
+        }
+    }
+
+    /// Hands an [`Error::ConnectionClosed`] to every waiting request whose id
+    /// does not satisfy the given predicate and removes its slot.
+    fn fail_slots(slots: &mut HashMap<i32, Slot>, retain: impl Fn(&i32) -> bool) {
+        slots.retain(|request_id, slot| {
+            if retain(request_id) {
+                return true;
+            }
+            match slot {
+                Slot::WaitForMessages(inbox) => {
+                    debug! { %request_id, "Failing request due to closed connection" };
+                    inbox.close(Some(Error::ConnectionClosed));
+                },
+                Slot::Streaming { inboxes, .. } => {
+                    debug! { %request_id, "Failing stream due to closed connection" };
+                    for inbox in inboxes.values() {
+                        inbox.close(Some(Error::ConnectionClosed));
+                    }
+                },
+                _ => {},
+            }
+            false
+        });
+    }
+
+    /// Dispatches received messages to their slots until the connection ends.
+    /// Returns a description of why it ended.
+    async fn dispatch_messages(&self, ws_stream: &mut SplitStream<S>) -> String {
+        *self.ping.lock().unwrap() = PingState::default();
+        let mut last_error = None;
+        let mut ping_timer = Delay::new(self.ping_interval());
+        loop {
+            let next = match future::select(pin!(self.receive_message_from(ws_stream)), &mut ping_timer).await {
+                Either::Left((next, _)) => next,
+                Either::Right(_) => {
+                    self.discard_expired(&mut *self.slots.lock().await);
+                    if let Err(error) = self.keep_alive(&mut ping_timer).await {
+                        warn! { %error, "Keepalive failed, considering the connection dead" };
+                        return format!("Keepalive failed: {error}");
+                    }
+                    continue
+                },
+            };
+            match next {
+                Ok(mut msg) => {
+                    // Only errors right before the end explain the disconnect
+                    last_error = None;
+                    if let Some(recorder) = self.recorder() {
+                        recorder.record_received(&msg);
+                    }
+                    if intercept_response(&self.interceptors, &mut msg) {
+                        self.dispatch(msg).await;
                     }
                 },
                 Err(Error::NoNextMessage) => {
                     info!("No next message available, closing receive loop");
-                    break
+                    return last_error.map_or_else(|| "The connection was closed".to_owned(), |error: Error| error.to_string());
+                },
+                Err(e) => {
+                    error!("Bad message: {:?}", e);
+                    last_error = Some(e);
                 },
-                Err(e) => error!("Bad message: {:?}", e),
             }
         }
     }
 
+    /// Delivers a message from the server to the waiting request or stream.
+    async fn dispatch(&self, msg: ServerMessage<Value>) {
+        if msg.code.is_success() && !msg.warnings.is_empty() {
+            warn! { request_id = ?msg.request_id, warnings = ?msg.warnings, "Server responded with warnings" };
+            self.warnings.broadcast(msg.clone().map_payload(|_| ()));
+        }
+        let mut slots = self.slots.lock().await;
+        if let Some(request_id) = msg.request_id {
+            if let Some(slot) = slots.get_mut(&request_id) {
+                match slot {
+                    Slot::EarlyMessages { .. } => self.store_early_message(&mut slots, request_id, msg),
+                    Slot::Detached { context, sent, .. } => {
+                        if context.verb == Verb::Put {
+                            self.telemetry.record_put_latency(sent.elapsed());
+                        }
+                        let context = context.clone();
+                        slots.remove(&request_id);
+                        let response = msg.map_payload(|_| ());
+                        if let Err(error) = response.clone().check() {
+                            warn! { %context, %error, "Unacknowledged request failed" };
+                            self.unacknowledged_errors.broadcast((context, response));
+                        }
+                    },
+                    Slot::WaitForMessages(inbox) => {
+                        if !inbox.push(StreamEvent::Message(msg)) {
+                            info!("Receiver for request id {} disconnected, removing the inbox...", request_id);
+                            slots.remove(&request_id);
+                        }
+                    },
+                    Slot::Streaming { inboxes, last } => {
+                        inboxes.retain(|subscriber_id, inbox| {
+                            let accepted = inbox.push(StreamEvent::Message(msg.clone()));
+                            if !accepted {
+                                debug! { %request_id, %subscriber_id, "Subscriber disconnected, removing its inbox" };
+                            }
+                            accepted
+                        });
+                        *last = Some(msg);
+                    },
+                }
+            } else {
+                self.store_early_message(&mut slots, request_id, msg);
+            }
+        } else {
+            debug!("Got message without request id from server: {:?}", msg);
+            self.notifications.broadcast(msg);
+        }
+    }
+
+    /// Fetches the recorder for the exchanged messages, if any.
+    fn recorder(&self) -> Option<SessionRecorder> {
+        self.settings.lock().unwrap().recorder.clone()
+    }
+
+    /// Fetches the interval after which the keepalive timer fires next.
+    fn ping_interval(&self) -> Duration {
+        self.settings.lock().unwrap().keepalive.unwrap_or_default().interval
+    }
+
+    /// Handles the keepalive timer, sending a ping if one is due. Fails if
+    /// the last ping was not answered in time or a ping could not be sent.
+    async fn keep_alive(&self, ping_timer: &mut Delay) -> Result<()> {
+        let Some(keepalive) = self.settings.lock().unwrap().keepalive else {
+            // Check again later, in case pings are enabled in the meantime
+            ping_timer.reset(self.ping_interval());
+            return Ok(());
+        };
+        let now = Instant::now();
+        let (sent, awaiting_pong) = {
+            let ping = self.ping.lock().unwrap();
+            (ping.sent, ping.awaiting_pong)
+        };
+        match sent {
+            Some(sent) if awaiting_pong => {
+                let elapsed = now.duration_since(sent);
+                if elapsed >= keepalive.timeout {
+                    return Err(Error::Timeout);
+                }
+                ping_timer.reset(keepalive.timeout - elapsed);
+            },
+            Some(sent) if now.duration_since(sent) < keepalive.interval => {
+                ping_timer.reset(keepalive.interval - now.duration_since(sent));
+            },
+            _ => {
+                trace!("Sending ping");
+                self.ws_sink.lock().await.send(Message::Ping(Vec::new())).await?;
+                let mut ping = self.ping.lock().unwrap();
+                ping.sent = Some(now);
+                ping.awaiting_pong = true;
+                ping_timer.reset(keepalive.interval.min(keepalive.timeout));
+            },
+        }
+        Ok(())
+    }
+
+    /// Records the answer to the last ping.
+    fn handle_pong(&self) {
+        let mut ping = self.ping.lock().unwrap();
+        if let (Some(sent), true) = (ping.sent, ping.awaiting_pong) {
+            let latency = sent.elapsed();
+            trace! { ?latency, "Got pong" };
+            ping.latency = Some(latency);
+            ping.awaiting_pong = false;
+        }
+    }
+
+    /// Discards expired early messages and unacknowledged requests that
+    /// were not answered in time, returning the latter's permits.
+    fn discard_expired(&self, slots: &mut HashMap<i32, Slot>) {
+        let (expiry, timeout) = {
+            let settings = self.settings.lock().unwrap();
+            (settings.early_message_limits.expiry, settings.timeout)
+        };
+        let now = Instant::now();
+        slots.retain(|request_id, slot| match slot {
+            Slot::EarlyMessages { messages, since } if now.duration_since(*since) >= expiry => {
+                debug! { %request_id, count = messages.len(), "Discarding expired early messages" };
+                false
+            },
+            Slot::Detached { context, sent, .. } if now.duration_since(*sent) >= timeout.unwrap_or(expiry) => {
+                warn! { %context, "Unacknowledged request was not answered in time, discarding it" };
+                false
+            },
+            _ => true,
+        });
+    }
+
+    /// Stores a message for a request id that has no waiting slot (yet),
+    /// discarding expired slots and enforcing the limits.
+    fn store_early_message(&self, slots: &mut HashMap<i32, Slot>, request_id: i32, msg: ServerMessage<Value>) {
+        let limits = self.settings.lock().unwrap().early_message_limits;
+        let now = Instant::now();
+        self.discard_expired(slots);
+
+        let total: usize = slots.values()
+            .map(|slot| match slot {
+                Slot::EarlyMessages { messages, .. } => messages.len(),
+                _ => 0,
+            })
+            .sum();
+        if total >= limits.total {
+            warn! { %request_id, %total, "Discarding early message, too many early messages in total" };
+            return;
+        }
+
+        if let Slot::EarlyMessages { messages, .. } = slots.entry(request_id).or_insert_with(|| Slot::EarlyMessages { messages: Vec::new(), since: now }) {
+            if messages.len() >= limits.per_request {
+                warn! { %request_id, count = messages.len(), "Discarding early message, too many early messages for this request id" };
+            } else {
+                messages.push(msg);
+            }
+        }
+    }
+
+    /// Re-establishes the connection, backing off according to the given
+    /// policy, and resubscribes all active streams. Returns `None` if the
+    /// connection could not be re-established.
+    async fn reconnect(&self, policy: &ReconnectPolicy, connect: &Connector<S>) -> Option<SplitStream<S>> {
+        // One-off requests will not be answered on the new connection, so we
+        // fail them right away.
+        {
+            let streams = self.streams.lock().await;
+            Self::fail_slots(&mut *self.slots.lock().await, |request_id| streams.contains_key(request_id));
+        }
+
+        let mut attempt = 0;
+        loop {
+            if policy.max_attempts.is_some_and(|max_attempts| attempt >= max_attempts) {
+                error! { %attempt, "Giving up on reconnecting" };
+                return None;
+            }
+            let delay = policy.delay(attempt);
+            info! { %attempt, ?delay, "Reconnecting" };
+            Delay::new(delay).await;
+            if self.closed.load(Ordering::Relaxed) {
+                return None;
+            }
+            self.set_state(ConnectionState::Connecting);
+            match connect().await {
+                Ok(web_socket) => {
+                    let (ws_sink, ws_stream) = web_socket.split();
+                    *self.ws_sink.lock().await = ws_sink;
+                    match self.resubscribe().await {
+                        Ok(()) => {
+                            info!("Reconnected");
+                            self.set_state(ConnectionState::Connected);
+                            return Some(ws_stream);
+                        },
+                        Err(error) => {
+                            warn! { %error, "Could not resubscribe streams" };
+                            self.set_state(ConnectionState::Disconnected { reason: error.to_string() });
+                        },
+                    }
+                },
+                Err(error) => {
+                    warn! { %error, "Could not reconnect" };
+                    self.set_state(ConnectionState::Disconnected { reason: error.to_string() });
+                },
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Re-sends all active STREAM requests under their original request ids
+    /// and notifies their consumers about the gap.
+    async fn resubscribe(&self) -> Result<()> {
+        let streams = self.streams.lock().await;
+        {
+            let slots = self.slots.lock().await;
+            for request_id in streams.keys() {
+                if let Some(Slot::Streaming { inboxes, .. }) = slots.get(request_id) {
+                    for inbox in inboxes.values() {
+                        inbox.push(StreamEvent::Gap);
+                    }
+                }
+            }
+        }
+        for message in streams.values() {
+            debug! { request_id = %message.request_id, path = ?message.path, "Resubscribing stream" };
+            self.send_message(message).await?;
+        }
+        Ok(())
+    }
+
     /// Receives a ServerMessage from the lighthouse.
-    #[tracing::instrument(skip(ws_stream))]
-    async fn receive_message_from<P>(ws_stream: &mut SplitStream<S>) -> Result<ServerMessage<P>>
+    #[tracing::instrument(skip(self, ws_stream))]
+    async fn receive_message_from<P>(&self, ws_stream: &mut SplitStream<S>) -> Result<ServerMessage<P>>
     where
         P: for<'de> Deserialize<'de> {
-        let bytes = Self::receive_raw_from(ws_stream).await?;
-        let message = rmp_serde::from_slice(&bytes)?;
+        let bytes = self.receive_raw_from(ws_stream).await?;
+        let message = rmp_serde::from_slice(&bytes).inspect_err(|_| self.telemetry.record_decode_error())?;
         Ok(message)
     }
 
     /// Receives raw bytes from the lighthouse via the WebSocket connection.
-    #[tracing::instrument(skip(ws_stream))]
-    async fn receive_raw_from(ws_stream: &mut SplitStream<S>) -> Result<Vec<u8>> {
+    #[tracing::instrument(skip(self, ws_stream))]
+    async fn receive_raw_from(&self, ws_stream: &mut SplitStream<S>) -> Result<Vec<u8>> {
         loop {
             let message = ws_stream.next().await.ok_or_else(|| Error::NoNextMessage)??;
             match message {
-                Message::Binary(bytes) => break Ok(bytes),
-                Message::Ping(_) => {}, // Ignore pings for now
+                Message::Binary(bytes) => {
+                    self.telemetry.record_received(bytes.len());
+                    break Ok(bytes)
+                },
+                Message::Ping(_) => {}, // Answered by tungstenite while reading
+                Message::Pong(_) => self.handle_pong(),
                 Message::Close(_) => break Err(Error::ConnectionClosed),
                 _ => warn!("Got non-binary message: {:?}", message),
             }
         }
     }
 
+    /// Creates a handle to the resource at the given path, whose payload
+    /// has the given type. Fails if the path is invalid.
+    pub fn resource<T>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<Resource<S, T>> {
+        Ok(Resource::new(self.clone(), into_path(path)?))
+    }
+
+    /// Creates an empty batch of requests, which are pipelined over the
+    /// connection once sent, see [`Batch`].
+    pub fn batch(&self) -> Batch<S> {
+        Batch::new(self.clone())
+    }
+
+    /// Creates a handle to the user's lighthouse model. Fails if the
+    /// username is not a valid path segment.
+    pub fn model(&self) -> Result<Resource<S, Model>> {
+        self.resource(["user", self.authentication.username.as_str(), "model"])
+    }
+
+    /// Creates a handle to the user's input endpoint. Fails if the username
+    /// is not a valid path segment.
+    ///
+    /// Note that this is the new API which not all clients may support.
+    pub fn input(&self) -> Result<Resource<S, InputEvent>> {
+        self.resource(["user", self.authentication.username.as_str(), "input"])
+    }
+
     /// Replaces the user's lighthouse model with the given frame.
     pub async fn put_model(&self, frame: Frame) -> Result<ServerMessage<()>> {
-        let username = self.authentication.username.clone();
-        self.put(&["user".into(), username, "model".into()], Model::Frame(frame)).await
+        self.telemetry.record_frame();
+        self.model()?.put(Model::Frame(frame)).await
+    }
+
+    /// Replaces the user's lighthouse model with the given frame without
+    /// waiting for the server's response, see [`Lighthouse::perform_nowait`].
+    pub async fn put_model_nowait(&self, frame: Frame) -> Result<()> {
+        self.telemetry.record_frame();
+        self.model()?.put_nowait(Model::Frame(frame)).await
+    }
+
+    /// Creates a sink for sending frames to the user's lighthouse model at a
+    /// high rate, see [`FrameSink`]. The rate can optionally be capped at the
+    /// given (positive) number of frames per second.
+    pub fn frame_sink(&self, max_fps: Option<f64>) -> FrameSink {
+        FrameSink::new(self.clone(), max_fps)
     }
 
     /// Requests a stream of events (including key/controller events) for the user's lighthouse model.
     pub async fn stream_model(&self) -> Result<impl Stream<Item = Result<ServerMessage<Model>>>> {
-        let username = self.authentication.username.clone();
-        self.stream(&["user".into(), username, "model".into()], ()).await
+        self.model()?.stream().await
     }
 
     /// Sends an input event to the user's input endpoint.
     /// 
     /// Note that this is the new API which not all clients may support.
     pub async fn put_input(&self, payload: InputEvent) -> Result<ServerMessage<()>> {
-        let username = self.authentication.username.clone();
-        self.put(&["user".into(), username, "input".into()], payload).await
+        self.input()?.put(payload).await
     }
 
     /// Streams input events from the user's input endpoint.
@@ -145,197 +646,473 @@ impl<S> Lighthouse<S>
     /// client or library does not support this, you may need to `stream_model`
     /// and parse `LegacyInputEvent`s from there.
     pub async fn stream_input(&self) -> Result<impl Stream<Item = Result<ServerMessage<InputEvent>>>> {
-        let username = self.authentication.username.clone();
+        Ok(self.stream_input_with_gaps().await?.try_filter_map(|event| future::ready(Ok(event.into_message()))))
+    }
+
+    /// Streams input events from the user's input endpoint, yielding a
+    /// [`StreamEvent::Gap`] whenever the connection was re-established.
+    /// Unless a default overflow policy is configured, no events are
+    /// dropped, since losing e.g. a key release would leave the key stuck.
+    pub async fn stream_input_with_gaps(&self) -> Result<impl Stream<Item = Result<StreamEvent<InputEvent>>>> {
+        let options = RequestOptions::new().with_overflow(self.defaults.overflow.unwrap_or(OverflowPolicy::Unbounded));
+        // Skip the persisted input, which is sent upon every (re)subscription
+        // (TODO: Should we handle this at the server level via some form of passthrough resources?)
+        let mut skip = true;
         Ok(
-            self.stream(&["user".into(), username, "input".into()], ()).await?
-                .skip(1) // Skip the persisted input (TODO: Should we handle this at the server level via some form of passthrough resources?)
+            self.resource::<Value>(self.input()?.path())?.stream_with_gaps(&options).await?
+                .filter(move |event| future::ready(match event {
+                    Ok(StreamEvent::Gap) => {
+                        skip = true;
+                        true
+                    },
+                    _ => !mem::replace(&mut skip, false),
+                }))
+                .map(|event| Ok(event?.decode_payload()?))
         )
     }
 
     /// Fetches lamp server metrics.
     pub async fn get_laser_metrics(&self) -> Result<ServerMessage<LaserMetrics>> {
-        self.get(&["metrics", "laser"]).await
+        self.get(path!["metrics", "laser"]).await
     }
 
     /// Combines PUT and CREATE. Requires CREATE and WRITE permission.
-    pub async fn post<P>(&self, path: &[impl AsRef<str> + Debug], payload: P) -> Result<ServerMessage<()>>
+    pub async fn post<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<()>>
     where
         P: Serialize {
         self.perform(&Verb::Post, path, payload).await
     }
 
     /// Updates the resource at the given path with the given payload. Requires WRITE permission.
-    pub async fn put<P>(&self, path: &[impl AsRef<str> + Debug], payload: P) -> Result<ServerMessage<()>>
+    pub async fn put<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<()>>
     where
         P: Serialize {
         self.perform(&Verb::Put, path, payload).await
     }
 
+    /// Updates the resource at the given path with the given payload without
+    /// waiting for the server's response, see [`Lighthouse::perform_nowait`].
+    pub async fn put_nowait<P>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<()>
+    where
+        P: Serialize {
+        self.perform_nowait(&Verb::Put, path, payload).await
+    }
+
     /// Creates a resource at the given path. Requires CREATE permission.
-    pub async fn create(&self, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
+    pub async fn create(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
         self.perform(&Verb::Create, path, ()).await
     }
 
     /// Deletes a resource at the given path. Requires DELETE permission.
-    pub async fn delete(&self, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
+    pub async fn delete(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
         self.perform(&Verb::Delete, path, ()).await
     }
 
     /// Creates a directory at the given path. Requires CREATE permission.
-    pub async fn mkdir(&self, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
+    pub async fn mkdir(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
         self.perform(&Verb::Mkdir, path, ()).await
     }
 
     /// Lists the directory tree at the given path. Requires READ permission.
-    pub async fn list(&self, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<DirectoryTree>> {
+    pub async fn list(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<DirectoryTree>> {
         self.perform(&Verb::List, path, ()).await
     }
 
     /// Gets the resource at the given path. Requires READ permission.
-    pub async fn get<R>(&self, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<R>>
+    pub async fn get<R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<R>>
     where
         R: for<'de> Deserialize<'de> {
         self.perform(&Verb::Get, path, ()).await
     }
 
     /// Links the given source to the given destination path.
-    pub async fn link(&self, src_path: &[impl AsRef<str> + Debug], dest_path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
-        self.perform(&Verb::Link, dest_path, src_path.iter().map(|s| s.as_ref().to_owned()).collect::<Vec<_>>()).await
+    pub async fn link(&self, src_path: impl TryInto<ResourcePath, Error: Into<Error>>, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.perform(&Verb::Link, dest_path, into_path(src_path)?).await
     }
 
     /// Unlinks the given source from the given destination path.
-    pub async fn unlink(&self, src_path: &[impl AsRef<str> + Debug], dest_path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
-        self.perform(&Verb::Unlink, dest_path, src_path.iter().map(|s| s.as_ref().to_owned()).collect::<Vec<_>>()).await
+    pub async fn unlink(&self, src_path: impl TryInto<ResourcePath, Error: Into<Error>>, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.perform(&Verb::Unlink, dest_path, into_path(src_path)?).await
     }
 
     /// Stops the given stream. **Should generally not be called manually**,
     /// since streams will automatically be stopped once dropped.
-    pub async fn stop(&self, request_id: i32, path: &[impl AsRef<str> + Debug]) -> Result<ServerMessage<()>> {
-        self.perform_with_id(request_id, &Verb::Stop, path, ()).await
+    pub async fn stop(&self, request_id: i32, path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        // Messages from the stream that are still in flight may arrive in
+        // place of the actual response, so we do not decode the payload
+        let response: ServerMessage<Value> = self.perform_with_id(request_id, &Verb::Stop, &into_path(path)?, (), &RequestOptions::default()).await?;
+        Ok(response.map_payload(|_| ()))
     }
 
     /// Performs a single request to the given path with the given payload.
-    #[tracing::instrument(skip(self, payload))]
-    pub async fn perform<P, R>(&self, verb: &Verb, path: &[impl AsRef<str> + Debug], payload: P) -> Result<ServerMessage<R>>
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn perform<P, R>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<ServerMessage<R>>
+    where
+        P: Serialize,
+        R: for<'de> Deserialize<'de> {
+        self.perform_with_options(verb, path, payload, &RequestOptions::default()).await
+    }
+
+    /// Performs a single request to the given path with the given payload,
+    /// overriding the default options with the given ones.
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn perform_with_options<P, R>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<ServerMessage<R>>
     where
         P: Serialize,
         R: for<'de> Deserialize<'de> {
         let request_id = self.next_request_id();
-        self.perform_with_id(request_id, verb, path, payload).await
+        self.perform_with_id(request_id, verb, &into_path(path)?, payload, options).await
+    }
+
+    /// Performs a single request to the given path with the given payload
+    /// without waiting for the server's response. This returns as soon as
+    /// the request is sent, error responses are reported asynchronously via
+    /// [`Lighthouse::unacknowledged_errors`].
+    ///
+    /// Requests the server does not answer within the default timeout (or,
+    /// without one, the expiry of the [`EarlyMessageLimits`]) are discarded
+    /// with a warning. This is checked whenever the keepalive timer fires.
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn perform_nowait<P>(&self, verb: &Verb, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<()>
+    where
+        P: Serialize {
+        assert_ne!(verb, &Verb::Stream, "Lighthouse::perform_nowait may only be used for one-off requests, use Lighthouse::stream for streaming.");
+        let request_id = self.next_request_id();
+        let context = RequestContext::new(verb, &into_path(path)?, request_id);
+        let permit = self.acquire_permit().await;
+        {
+            let mut slots = self.slots.lock().await;
+            if self.terminated.load(Ordering::Relaxed) {
+                return Err(Error::ConnectionClosed.with_context(context));
+            }
+            slots.insert(request_id, Slot::Detached { context: context.clone(), sent: Instant::now(), _permit: permit });
+        }
+        if let Err(error) = self.send_request(request_id, verb, &context.path, &self.defaults.meta, payload).await {
+            self.slots.lock().await.remove(&request_id);
+            return Err(error.with_context(context));
+        }
+        Ok(())
+    }
+
+    /// Streams the errors from failed requests that were sent without
+    /// waiting for the response, e.g. via [`Lighthouse::perform_nowait`].
+    /// Only errors occurring after subscribing are reported.
+    pub fn unacknowledged_errors(&self) -> impl Stream<Item = Error> {
+        self.unacknowledged_errors.subscribe().filter_map(|(context, response)| future::ready(response.check().err().map(|error| error.with_context(context))))
+    }
+
+    /// Streams the successful responses carrying warnings, which are
+    /// otherwise only logged. Only responses arriving after subscribing are
+    /// yielded.
+    pub fn warnings(&self) -> impl Stream<Item = ServerMessage<()>> {
+        self.warnings.subscribe()
+    }
+
+    /// Streams the messages pushed by the server without a request id, e.g.
+    /// broadcasts such as maintenance notices. Only messages arriving after
+    /// subscribing are yielded.
+    pub fn notifications(&self) -> impl Stream<Item = ServerMessage<Value>> {
+        self.notifications.subscribe()
     }
 
     /// Performs a single request to the given path with the given request id.
-    #[tracing::instrument(skip(self, payload))]
-    async fn perform_with_id<P, R>(&self, request_id: i32, verb: &Verb, path: &[impl AsRef<str> + Debug], payload: P) -> Result<ServerMessage<R>>
+    #[tracing::instrument(skip(self, path, payload), fields(%path))]
+    async fn perform_with_id<P, R>(&self, request_id: i32, verb: &Verb, path: &ResourcePath, payload: P, options: &RequestOptions) -> Result<ServerMessage<R>>
     where
         P: Serialize,
         R: for<'de> Deserialize<'de> {
         assert_ne!(verb, &Verb::Stream, "Lighthouse::perform may only be used for one-off requests, use Lighthouse::stream for streaming.");
-        self.send_request(request_id, verb, path, payload).await?;
-        let response = self.receive_single(request_id).await?.check()?.decode_payload()?;
-        Ok(response)
+        let mut request_id = request_id;
+        self.try_perform_with_id(&mut request_id, verb, path, payload, options).await
+            .map_err(|error| error.with_context(RequestContext::new(verb, path, request_id)))
+    }
+
+    /// Performs a single request to the given path with the given request id,
+    /// retrying according to the options. Retries are sent with a new request
+    /// id (except for STOPs, which refer to their stream by id), which is
+    /// written back to `request_id`. Errors lack the request context.
+    async fn try_perform_with_id<P, R>(&self, request_id: &mut i32, verb: &Verb, path: &ResourcePath, payload: P, options: &RequestOptions) -> Result<ServerMessage<R>>
+    where
+        P: Serialize,
+        R: for<'de> Deserialize<'de> {
+        let mut options = options.or(&self.defaults);
+        options.timeout = options.timeout.or(self.default_timeout());
+        let mut attempts = 1;
+        loop {
+            let response = with_timeout(options.timeout, async {
+                let _permit = self.acquire_permit().await;
+                // Register the slot before sending, so neither the response
+                // nor a concurrent reconnect can miss it
+                let response = self.receive_single(*request_id).await?;
+                self.send_request(*request_id, verb, path, &options.meta, &payload).await?;
+                let sent = Instant::now();
+                let response = response.await?;
+                if verb == &Verb::Put {
+                    self.telemetry.record_put_latency(sent.elapsed());
+                }
+                Ok(response)
+            }).await;
+            if let Err(Error::Timeout) = response {
+                // Make sure the slot does not outlive the request
+                self.slots.lock().await.remove(request_id);
+            }
+            match response?.check() {
+                Ok(response) => return Ok(response.decode_payload()?),
+                Err(Error::Server { code, .. }) if options.retry.as_ref().is_some_and(|retry| retry.should_retry(verb, code, attempts)) => {
+                    let delay = options.retry.as_ref().unwrap().delay(attempts);
+                    warn! { %request_id, %code, %attempts, ?delay, "Request failed, retrying" };
+                    Delay::new(delay).await;
+                    attempts += 1;
+                    if verb != &Verb::Stop {
+                        *request_id = self.next_request_id();
+                    }
+                },
+                Err(Error::Server { code, message, warnings, .. }) => return Err(Error::Server { code, message, warnings, attempts }),
+                Err(error) => return Err(error),
+            }
+        }
     }
     
     /// Performs a STREAM request to the given path with the given payload.
     /// Automatically sends a STOP once dropped.
-    #[tracing::instrument(skip(self, payload))]
-    pub async fn stream<P, R>(&self, path: &[impl AsRef<str> + Debug], payload: P) -> Result<impl Stream<Item = Result<ServerMessage<R>>>>
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn stream<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P) -> Result<impl Stream<Item = Result<ServerMessage<R>>>>
     where
         P: Serialize,
         R: for<'de> Deserialize<'de> {
-        let request_id = self.next_request_id();
-        let path: Vec<String> = path.into_iter().map(|s| s.as_ref().to_string()).collect();
-        self.send_request(request_id, &Verb::Stream, &path, payload).await?;
-        let stream = self.receive_streaming(request_id).await?;
-        Ok(stream.map(|m| Ok(m?.check()?.decode_payload()?)).guard({
-            // Stop the stream on drop
+        self.stream_with_options(path, payload, &RequestOptions::default()).await
+    }
+
+    /// Performs a STREAM request to the given path with the given payload,
+    /// overriding the default options (e.g. the buffer size and overflow
+    /// policy) with the given ones. Automatically sends a STOP once dropped.
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn stream_with_options<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<impl Stream<Item = Result<ServerMessage<R>>>>
+    where
+        P: Serialize,
+        R: for<'de> Deserialize<'de> {
+        Ok(self.stream_with_gaps(path, payload, options).await?.try_filter_map(|event| future::ready(Ok(event.into_message()))))
+    }
+
+    /// Performs a STREAM request to the given path with the given payload and
+    /// options, yielding a [`StreamEvent::Gap`] whenever the connection was
+    /// re-established. Automatically sends a STOP once dropped.
+    ///
+    /// Streams of the same path with the same payload and META share a
+    /// single subscription on the server, which is only stopped once the
+    /// last of them is dropped. Subscribers joining an active stream
+    /// receive its most recent message first.
+    #[tracing::instrument(skip(self, path, payload))]
+    pub async fn stream_with_gaps<P, R>(&self, path: impl TryInto<ResourcePath, Error: Into<Error>>, payload: P, options: &RequestOptions) -> Result<impl Stream<Item = Result<StreamEvent<R>>>>
+    where
+        P: Serialize,
+        R: for<'de> Deserialize<'de> {
+        let options = options.or(&self.defaults);
+        let path = into_path(path)?;
+        // Only used if no matching stream is active yet, but allocated
+        // upfront so that early errors carry a request context too
+        let new_request_id = self.next_request_id();
+        let context = RequestContext::new(&Verb::Stream, &path, new_request_id);
+        let payload = to_value(payload).map_err(|error| Error::from(error).with_context(context.clone()))?;
+        let subscriber_id = self.subscriber_id.fetch_add(1, Ordering::Relaxed);
+        let (inbox, rx) = inbox(options.buffer.unwrap_or(DEFAULT_BUFFER), options.overflow.unwrap_or_default());
+
+        let request_id = {
+            let mut streams = self.streams.lock().await;
+            let existing = streams.iter()
+                .find(|(_, message)| message.path == path.segments() && message.payload == payload && message.meta == options.meta)
+                .map(|(request_id, _)| *request_id);
+            let mut slots = self.slots.lock().await;
+            if self.terminated.load(Ordering::Relaxed) {
+                return Err(Error::ConnectionClosed.with_context(context));
+            }
+            match existing.and_then(|request_id| slots.get_mut(&request_id).map(|slot| (request_id, slot))) {
+                Some((request_id, Slot::Streaming { inboxes, last })) => {
+                    debug! { %request_id, %subscriber_id, "Joining active stream" };
+                    if let Some(last) = last {
+                        inbox.push(StreamEvent::Message(last.clone()));
+                    }
+                    inboxes.insert(subscriber_id, inbox);
+                    request_id
+                },
+                _ => {
+                    let request_id = new_request_id;
+                    let message = self.request_message(request_id, &Verb::Stream, &path, &options.meta, payload);
+                    // Register the stream before sending, so neither a
+                    // concurrent reconnect nor the response can miss it
+                    slots.insert(request_id, Slot::Streaming { inboxes: HashMap::from([(subscriber_id, inbox)]), last: None });
+                    streams.insert(request_id, message.clone());
+                    self.telemetry.set_streams(streams.len());
+                    drop(slots);
+                    debug! { %request_id, "Sending request" };
+                    if let Err(error) = self.send_message(&message).await {
+                        streams.remove(&request_id);
+                        self.telemetry.set_streams(streams.len());
+                        self.slots.lock().await.remove(&request_id);
+                        return Err(error.with_context(context));
+                    }
+                    request_id
+                },
+            }
+        };
+        self.telemetry.add_stream_subscribers(1);
+
+        let context = RequestContext::new(&Verb::Stream, &path, request_id);
+        Ok(rx.map(move |event| {
+            let event = event.and_then(|event| Ok(match event {
+                StreamEvent::Message(message) => StreamEvent::Message(message.check()?.decode_payload()?),
+                StreamEvent::Gap => StreamEvent::Gap,
+            }));
+            event.map_err(|error| error.with_context(context.clone()))
+        }).guard({
             let this = (*self).clone();
             move || {
-                tokio::spawn(async move {
-                    if let Err(error) = this.stop(request_id, &path).await {
-                        error! { ?path, %error, "Could not STOP stream" };
-                    }
+                this.telemetry.add_stream_subscribers(-1);
+                spawn_with(this.spawn, async move {
+                    this.unsubscribe(request_id, subscriber_id, path).await;
                 });
             }
         }))
     }
 
+    /// Removes the given subscriber from a stream and sends a STOP once the
+    /// last subscriber is gone.
+    async fn unsubscribe(&self, request_id: i32, subscriber_id: usize, path: ResourcePath) {
+        {
+            let mut streams = self.streams.lock().await;
+            let mut slots = self.slots.lock().await;
+            if let Some(Slot::Streaming { inboxes, .. }) = slots.get_mut(&request_id) {
+                inboxes.remove(&subscriber_id);
+                if !inboxes.is_empty() {
+                    return;
+                }
+            }
+            // The slot is removed before sending the STOP, since the latter
+            // registers a slot under the same id
+            slots.remove(&request_id);
+            if streams.remove(&request_id).is_none() {
+                // The stream already ended along with the connection
+                return;
+            }
+            self.telemetry.set_streams(streams.len());
+        }
+        if let Err(error) = self.stop(request_id, &path).await {
+            error! { %path, error = %error.without_context(), "Could not STOP stream" };
+        }
+    }
+
     /// Sends a request to the given path with the given payload.
-    async fn send_request<P>(&self, request_id: i32, verb: &Verb, path: &[impl AsRef<str> + Debug], payload: P) -> Result<i32>
+    async fn send_request<P>(&self, request_id: i32, verb: &Verb, path: &ResourcePath, meta: &HashMap<String, String>, payload: P) -> Result<i32>
     where
         P: Serialize {
-        let path = path.into_iter().map(|s| s.as_ref().to_string()).collect();
         debug! { %request_id, "Sending request" };
-        self.send_message(&ClientMessage {
+        self.send_message(&self.request_message(request_id, verb, path, meta, payload)).await?;
+        Ok(request_id)
+    }
+
+    /// Constructs a request to the given path with the given payload.
+    fn request_message<P>(&self, request_id: i32, verb: &Verb, path: &ResourcePath, meta: &HashMap<String, String>, payload: P) -> ClientMessage<P> {
+        ClientMessage {
             request_id,
             authentication: self.authentication.clone(),
-            path,
-            meta: HashMap::new(),
+            path: path.segments().to_vec(),
+            meta: meta.clone(),
             verb: verb.clone(),
             payload
-        }).await?;
-        Ok(request_id)
+        }
     }
 
     /// Sends a generic message to the lighthouse.
     async fn send_message<P>(&self, message: &ClientMessage<P>) -> Result<()>
     where
         P: Serialize {
-        self.send_raw(rmp_serde::to_vec_named(message)?).await
-    }
-
-    /// Receives a single response for the given request id.
-    #[tracing::instrument(skip(self))]
-    async fn receive_single<R>(&self, request_id: i32) -> Result<ServerMessage<R>>
-    where
-        R: for<'de> Deserialize<'de> {
-        let mut rx = self.receive(request_id).await?;
-        rx.next().await.ok_or_else(|| Error::Custom(format!("No response for {}", request_id)))?
+        let mut bytes = rmp_serde::to_vec_named(message)?;
+        let mut verb = message.verb.clone();
+        if !self.interceptors.is_empty() {
+            // Round-trip through MessagePack rather than `to_value`, which
+            // would encode structs as arrays
+            let mut message: ClientMessage<Value> = rmp_serde::from_slice(&bytes)?;
+            if let Some((index, mut response)) = intercept_request(&self.interceptors, &mut message) {
+                debug! { request_id = %message.request_id, "Request answered by interceptor" };
+                response.request_id.get_or_insert(message.request_id);
+                if intercept_response(&self.interceptors[..index], &mut response) {
+                    self.dispatch(response).await;
+                }
+                return Ok(());
+            }
+            bytes = rmp_serde::to_vec_named(&message)?;
+            verb = message.verb;
+        }
+        self.send_raw(bytes).await?;
+        self.telemetry.record_request(&verb);
+        Ok(())
     }
 
-    /// Receives a stream of responses for the given request id.
+    /// Registers a slot for the given request id and returns a future
+    /// receiving its single response.
     #[tracing::instrument(skip(self))]
-    async fn receive_streaming<R>(&self, request_id: i32) -> Result<impl Stream<Item = Result<ServerMessage<R>>>>
-    where
-        R: for<'de> Deserialize<'de> {
-        self.receive(request_id).await
+    async fn receive_single(&self, request_id: i32) -> Result<impl Future<Output = Result<ServerMessage<Value>>>> {
+        let rx = self.receive(request_id, &RequestOptions::default()).await?;
+        Ok(async move {
+            let mut rx = pin!(rx.try_filter_map(|event| future::ready(Ok(event.into_message()))));
+            rx.next().await.ok_or_else(|| Error::custom("No response received"))?
+        })
     }
 
-    async fn receive<R>(&self, request_id: i32) -> Result<impl Stream<Item = Result<ServerMessage<R>>>>
-    where
-        R: for<'de> Deserialize<'de> {
-        let rx = {
-            let capacity = 4;
-            let (tx, rx) = {
-                let mut slots = self.slots.lock().await;
-                if let Some(Slot::EarlyMessages(msgs)) = slots.get_mut(&request_id) {
-                    let (mut tx, rx) = mpsc::channel(capacity.min(msgs.len()));
-                    for msg in msgs.drain(..) {
-                        tx.feed(msg).await.map_err(|e| Error::Custom(format!("Could not feed tx with early message: {}", e)))?;
-                    } 
-                    tx.flush().await.map_err(|e| Error::Custom(format!("Could not flush tx with early messages: {}", e)))?;
-                    (tx, rx)
-                } else {
-                    mpsc::channel(capacity)
-                }
-            };
-            self.slots.lock().await.insert(request_id, Slot::WaitForMessages(tx));
-            rx
-        };
-        Ok(rx.map(|s| Ok(s.decode_payload()?)).guard({
+    /// Receives responses for the given request id, removing the slot once
+    /// the returned stream is dropped.
+    async fn receive(&self, request_id: i32, options: &RequestOptions) -> Result<impl Stream<Item = Result<StreamEvent<Value>>>> {
+        let rx = self.register_slot(request_id, options).await?;
+        Ok(rx.guard({
+            let spawn = self.spawn;
             let slots = self.slots.clone();
             move || {
-                tokio::spawn(async move {
+                spawn_with(spawn, async move {
                     let mut slots = slots.lock().await;
-                    slots.remove(&request_id);
+                    // The request id may have been registered again in the
+                    // meantime, e.g. by a STOP, whose slot must be kept
+                    if matches!(slots.get(&request_id), Some(Slot::WaitForMessages(inbox)) if inbox.is_receiver_dropped()) {
+                        slots.remove(&request_id);
+                    }
                 });
             }
         }))
     }
 
+    /// Registers a slot for the given request id and returns the receiver
+    /// for its messages.
+    async fn register_slot(&self, request_id: i32, options: &RequestOptions) -> Result<InboxReceiver> {
+        let capacity = options.buffer.unwrap_or(DEFAULT_BUFFER);
+        let overflow = options.overflow.unwrap_or_default();
+        let (inbox, rx) = inbox(capacity, overflow);
+        let mut slots = self.slots.lock().await;
+        if self.terminated.load(Ordering::Relaxed) {
+            return Err(Error::ConnectionClosed);
+        }
+        if let Some(Slot::EarlyMessages { messages, .. }) = slots.get_mut(&request_id) {
+            for msg in messages.drain(..) {
+                inbox.push(StreamEvent::Message(msg));
+            }
+        }
+        slots.insert(request_id, Slot::WaitForMessages(inbox));
+        Ok(rx)
+    }
+
+    /// Spawns the given future as a background task.
+    pub(crate) fn spawn(&self, future: impl Future<Output = ()> + Send + 'static) {
+        spawn_with(self.spawn, future);
+    }
+
     /// Sends raw bytes to the lighthouse via the WebSocket connection.
     async fn send_raw(&self, bytes: impl Into<Vec<u8>> + Debug) -> Result<()> {
-        Ok(self.ws_sink.lock().await.send(Message::Binary(bytes.into())).await?)
+        let bytes = bytes.into();
+        if let Some(recorder) = self.recorder() {
+            recorder.record_sent(&bytes);
+        }
+        let len = bytes.len();
+        self.ws_sink.lock().await.send(Message::Binary(bytes)).await?;
+        self.telemetry.record_sent(len);
+        Ok(())
     }
 
     /// Fetches the next request id.
@@ -348,14 +1125,103 @@ impl<S> Lighthouse<S>
         &self.authentication
     }
 
+    /// Sets the limits for messages whose request id has no registered slot,
+    /// which applies to the entire connection.
+    pub fn set_early_message_limits(&self, limits: EarlyMessageLimits) {
+        self.settings.lock().unwrap().early_message_limits = limits;
+    }
+
+    /// Configures the keepalive pings, which applies to the entire
+    /// connection. `None` disables pings. Changes take effect after the
+    /// next scheduled ping.
+    pub fn set_keepalive(&self, keepalive: Option<Keepalive>) {
+        self.settings.lock().unwrap().keepalive = keepalive;
+    }
+
+    /// Starts recording the messages exchanged over the connection with the
+    /// given recorder, which applies to the entire connection. `None` stops
+    /// recording.
+    pub fn set_recorder(&self, recorder: Option<SessionRecorder>) {
+        self.settings.lock().unwrap().recorder = recorder;
+    }
+
+    /// Sets the maximum number of requests awaiting their response, which
+    /// applies to the entire connection. `None` removes the limit, a limit
+    /// of 0 is treated as 1. See
+    /// [`LighthouseBuilder::max_in_flight`](crate::LighthouseBuilder::max_in_flight).
+    pub fn set_max_in_flight(&self, max_in_flight: Option<usize>) {
+        self.settings.lock().unwrap().max_in_flight = max_in_flight.map(|limit| limit.max(1));
+        self.limiter.wake_all();
+    }
+
+    /// Fetches the maximum number of requests awaiting their response.
+    pub fn max_in_flight(&self) -> Option<usize> {
+        self.settings.lock().unwrap().max_in_flight
+    }
+
+    /// Waits until another request may be in flight.
+    async fn acquire_permit(&self) -> Permit {
+        self.limiter.acquire(|| self.settings.lock().unwrap().max_in_flight).await
+    }
+
+    /// Streams the state of the connection, starting with the current state
+    /// and followed by every transition. The stream ends once the connection
+    /// is closed for good.
+    pub fn state(&self) -> impl Stream<Item = ConnectionState> {
+        self.state.subscribe()
+    }
+
+    /// Fetches the current state of the connection.
+    pub fn current_state(&self) -> ConnectionState {
+        self.state.get()
+    }
+
+    /// Takes a snapshot of the connection's statistics, e.g. the number of
+    /// requests and the PUT latency. These are shared by all handles to the
+    /// connection and cover its entire lifetime, including reconnects.
+    pub fn stats(&self) -> Stats {
+        Stats { in_flight: self.limiter.in_flight(), ..self.telemetry.snapshot() }
+    }
+
+    /// Fetches the round-trip latency measured by the most recent keepalive
+    /// ping, if any.
+    pub fn latency(&self) -> Option<Duration> {
+        self.ping.lock().unwrap().latency
+    }
+
+    /// Fetches the default timeout for requests.
+    pub fn default_timeout(&self) -> Option<Duration> {
+        self.settings.lock().unwrap().timeout
+    }
+
+    /// Sets the default timeout for requests made through any handle to
+    /// this connection. `None` waits indefinitely.
+    pub fn set_default_timeout(&self, timeout: Option<Duration>) {
+        self.settings.lock().unwrap().timeout = timeout;
+    }
+
     /// Closes the WebSocket connection gracefully with a close message. While
     /// the server will usually also handle abruptly closed connections
     /// properly, it is recommended to always close the [``Lighthouse``].
     pub async fn close(&self) -> Result<()> {
+        self.closed.store(true, Ordering::Relaxed);
+        self.set_state(ConnectionState::Closed);
         Ok(self.ws_sink.lock().await.close().await?)
     }
 }
 
+/// Runs the given future, failing with [`Error::Timeout`] if it does not
+/// complete within the given duration.
+async fn with_timeout<T>(timeout: Option<Duration>, future: impl Future<Output = Result<T>>) -> Result<T> {
+    let Some(timeout) = timeout else {
+        return future.await;
+    };
+    match future::select(pin!(future), Delay::new(timeout)).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::Timeout),
+    }
+}
+
 // For some reason `#[derive(Clone)]` adds the trait bound `S: Clone`, despite
 // not actually being needed since the WebSocket sink is already wrapped in an
 // `Arc`, therefore we implement `Clone` manually.
@@ -365,8 +1231,46 @@ impl<S> Clone for Lighthouse<S> {
         Self {
             ws_sink: self.ws_sink.clone(),
             slots: self.slots.clone(),
+            streams: self.streams.clone(),
             authentication: self.authentication.clone(),
+            defaults: self.defaults.clone(),
             request_id: self.request_id.clone(),
+            subscriber_id: self.subscriber_id.clone(),
+            closed: self.closed.clone(),
+            settings: self.settings.clone(),
+            ping: self.ping.clone(),
+            unacknowledged_errors: self.unacknowledged_errors.clone(),
+            warnings: self.warnings.clone(),
+            notifications: self.notifications.clone(),
+            state: self.state.clone(),
+            telemetry: self.telemetry.clone(),
+            limiter: self.limiter.clone(),
+            interceptors: self.interceptors.clone(),
+            spawn: self.spawn,
+            terminated: self.terminated.clone(),
         }
     }
 }
+
+#[cfg(all(test, feature = "mock", feature = "tokio"))]
+mod tests {
+    use std::time::Duration;
+
+    use lighthouse_protocol::Authentication;
+
+    use crate::{Error, LighthouseBuilder, MockLighthouse, TokioSpawner};
+
+    #[tokio::test]
+    async fn removes_slots_of_timed_out_requests() {
+        let mock = MockLighthouse::new();
+        let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+            .timeout(Duration::from_millis(20))
+            .spawner::<TokioSpawner>()
+            .build(mock.connect())
+            .unwrap();
+        mock.ignore_requests(1);
+        assert!(matches!(lh.post("/a", 1).await.unwrap_err().without_context(), Error::Timeout));
+        assert!(lh.slots.lock().await.is_empty());
+        assert_eq!(lh.stats().in_flight, 0);
+    }
+}
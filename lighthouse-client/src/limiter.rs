@@ -0,0 +1,88 @@
+use std::{mem, sync::{Arc, Mutex}, task::{Poll, Waker}};
+
+use futures::future;
+
+/// Limits the number of requests awaiting their response, see
+/// [`LighthouseBuilder::max_in_flight`](crate::LighthouseBuilder::max_in_flight).
+#[derive(Debug, Default)]
+pub(crate) struct Limiter {
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    /// The number of outstanding permits.
+    in_flight: usize,
+    /// The tasks waiting for a permit.
+    waiters: Vec<Waker>,
+}
+
+/// Permission to have a request in flight, which is returned once dropped.
+#[derive(Debug)]
+pub(crate) struct Permit {
+    limiter: Arc<Limiter>,
+}
+
+impl Limiter {
+    /// Waits until fewer requests than the given limit (`None` meaning
+    /// unlimited) are in flight and acquires a permit. The limit is
+    /// re-evaluated whenever the task is woken, so it may change while
+    /// waiting.
+    pub(crate) async fn acquire(self: &Arc<Self>, limit: impl Fn() -> Option<usize>) -> Permit {
+        future::poll_fn(|cx| {
+            let limit = limit();
+            let mut state = self.state.lock().unwrap();
+            if limit.is_none_or(|limit| state.in_flight < limit) {
+                state.in_flight += 1;
+                Poll::Ready(Permit { limiter: self.clone() })
+            } else {
+                state.waiters.push(cx.waker().clone());
+                Poll::Pending
+            }
+        }).await
+    }
+
+    /// The number of requests currently in flight.
+    pub(crate) fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Wakes all waiting tasks, e.g. after the limit was raised. Waking all
+    /// of them (rather than just the next one) ensures that no permit is
+    /// lost to a waiter that is dropped after being woken.
+    pub(crate) fn wake_all(&self) {
+        let waiters = mem::take(&mut self.state.lock().unwrap().waiters);
+        for waker in waiters {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().in_flight -= 1;
+        self.limiter.wake_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::{executor::block_on, FutureExt};
+
+    use super::Limiter;
+
+    #[test]
+    fn limits_permits() {
+        let limiter = Arc::new(Limiter::default());
+        let first = block_on(limiter.acquire(|| Some(2)));
+        let _second = block_on(limiter.acquire(|| Some(2)));
+        assert_eq!(limiter.in_flight(), 2);
+        assert!(limiter.acquire(|| Some(2)).now_or_never().is_none());
+        assert!(limiter.acquire(|| None).now_or_never().is_some());
+        drop(first);
+        assert!(limiter.acquire(|| Some(2)).now_or_never().is_some());
+        assert_eq!(limiter.in_flight(), 1);
+    }
+}
@@ -0,0 +1,504 @@
+use std::{collections::{BTreeMap, HashMap, HashSet, VecDeque}, fmt::Debug, mem, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll}};
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{channel::mpsc::{self, UnboundedReceiver, UnboundedSender}, Sink, Stream};
+use lighthouse_protocol::{from_value, to_value, ClientMessage, DirectoryTree, Frame, InputEvent, Model, ResourcePath, ServerMessage, StatusCode, Value, Verb};
+use tracing::{debug, warn};
+
+/// An in-process lighthouse server for testing client code without network
+/// access. Connections are created via [`MockLighthouse::connect`] and speak
+/// the same MessagePack protocol as the real server.
+///
+/// The server keeps a resource tree supporting all verbs, including STREAM
+/// and STOP, records every request for later inspection and can inject
+/// updates, e.g. input events, as if they were sent by another client.
+/// Responses echo the META of their request.
+///
+/// ```
+/// # use lighthouse_client::{LighthouseBuilder, MockLighthouse, TokioSpawner, protocol::{Authentication, Frame}};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() -> lighthouse_client::Result<()> {
+/// let mock = MockLighthouse::new();
+/// mock.add_user("alice");
+/// let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+///     .spawner::<TokioSpawner>()
+///     .build(mock.connect())?;
+/// lh.put_model(Frame::empty()).await?;
+/// assert_eq!(mock.frames("alice"), vec![Frame::empty()]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct MockLighthouse {
+    state: Arc<Mutex<State>>,
+}
+
+/// The state of a [`MockLighthouse`].
+#[derive(Default)]
+struct State {
+    /// The root directory of the resource tree.
+    root: BTreeMap<String, Node>,
+    /// The links from source paths to destination paths.
+    links: HashMap<Vec<String>, Vec<Vec<String>>>,
+    /// The active STREAM requests.
+    subscriptions: Vec<Subscription>,
+    /// The senders to the open connections, keyed by connection id.
+    connections: HashMap<usize, UnboundedSender<Message>>,
+    /// The next connection id.
+    next_connection: usize,
+    /// Every request received so far, in order.
+    requests: Vec<ClientMessage<Value>>,
+    /// The PUT requests applied to the resource tree so far, in order.
+    puts: Vec<ClientMessage<Value>>,
+    /// The status codes with which to fail the next requests, in order.
+    failures: VecDeque<StatusCode>,
+    /// The number of next requests to leave unanswered.
+    ignored: usize,
+    /// Whether to leave pings unanswered.
+    ignore_pings: bool,
+    /// The warnings to attach to the next response.
+    warnings: Vec<String>,
+}
+
+/// A node in the resource tree.
+enum Node {
+    Resource(Value),
+    Directory(BTreeMap<String, Node>),
+}
+
+/// An active STREAM request.
+struct Subscription {
+    connection: usize,
+    request_id: i32,
+    path: Vec<String>,
+}
+
+/// The response to a request, consisting of a status code and a payload.
+type Response = (i32, Value);
+
+impl MockLighthouse {
+    /// Creates a new mock server with an empty resource tree.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the resources of the given user, i.e. their model (holding
+    /// an empty frame) and their input.
+    pub fn add_user(&self, username: &str) {
+        let model = to_value(Model::Frame(Frame::empty())).unwrap();
+        let mut state = self.state.lock().unwrap();
+        state.insert(&user_path(username, "model"), model);
+        state.insert(&user_path(username, "input"), Value::Nil);
+    }
+
+    /// Opens a new connection to the server.
+    pub fn connect(&self) -> MockWebSocket {
+        let (tx, rx) = mpsc::unbounded();
+        let mut state = self.state.lock().unwrap();
+        let connection = state.next_connection;
+        state.next_connection += 1;
+        state.connections.insert(connection, tx);
+        MockWebSocket { server: self.clone(), connection, incoming: rx, closed: false }
+    }
+
+    /// Drops all open connections as if the server went away. Clients
+    /// observe the connection closing without a closing handshake.
+    pub fn disconnect_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connections.clear();
+        state.subscriptions.clear();
+    }
+
+    /// Updates the resource at the given path as if another client PUT the
+    /// given payload, notifying streams. Creates the resource if needed.
+    pub fn put(&self, path: impl TryInto<ResourcePath, Error: Debug>, payload: impl serde::Serialize) {
+        let path = path.try_into().expect("Invalid resource path").into_segments();
+        let payload = to_value(payload).expect("Could not encode payload");
+        self.state.lock().unwrap().update(&path, payload);
+    }
+
+    /// Sends the given input event to the given user's input, as if it came
+    /// from a frontend.
+    pub fn inject_input(&self, username: &str, event: InputEvent) {
+        self.put(user_path(username, "input"), event);
+    }
+
+    /// Pushes a message without a request id to all open connections, as
+    /// the server does for broadcasts.
+    pub fn notify(&self, code: impl Into<StatusCode>, payload: impl serde::Serialize) {
+        self.broadcast(None, code.into(), payload);
+    }
+
+    /// Pushes a message with the given request id to all open connections,
+    /// e.g. to simulate messages arriving before their request was sent.
+    pub fn notify_request(&self, request_id: i32, code: impl Into<StatusCode>, payload: impl serde::Serialize) {
+        self.broadcast(Some(request_id), code.into(), payload);
+    }
+
+    /// Pushes the given raw bytes to all open connections, e.g. to simulate
+    /// messages the client cannot decode.
+    pub fn notify_raw(&self, bytes: Vec<u8>) {
+        let mut state = self.state.lock().unwrap();
+        let connections = state.connections.keys().copied().collect::<Vec<_>>();
+        for connection in connections {
+            state.send_raw(connection, Message::Binary(bytes.clone()));
+        }
+    }
+
+    /// Pushes a message with the given request id to all open connections.
+    fn broadcast(&self, request_id: Option<i32>, code: StatusCode, payload: impl serde::Serialize) {
+        let payload = to_value(payload).expect("Could not encode payload");
+        let mut state = self.state.lock().unwrap();
+        let connections = state.connections.keys().copied().collect::<Vec<_>>();
+        for connection in connections {
+            state.send(connection, request_id, code, HashMap::new(), Vec::new(), payload.clone());
+        }
+    }
+
+    /// Fetches the resource at the given path, if it exists.
+    pub fn get(&self, path: impl TryInto<ResourcePath, Error: Debug>) -> Option<Value> {
+        let path = path.try_into().expect("Invalid resource path").into_segments();
+        match self.state.lock().unwrap().node(&path) {
+            Some(Node::Resource(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Fails the next `count` requests with the given status code without
+    /// handling them, e.g. to simulate an overloaded server via 503.
+    pub fn fail_requests(&self, code: impl Into<StatusCode>, count: usize) {
+        self.state.lock().unwrap().failures.extend(std::iter::repeat_n(code.into(), count));
+    }
+
+    /// Leaves the next `count` requests unanswered without handling them,
+    /// e.g. to simulate timeouts.
+    pub fn ignore_requests(&self, count: usize) {
+        self.state.lock().unwrap().ignored += count;
+    }
+
+    /// Leaves pings unanswered while enabled, e.g. to simulate a dead
+    /// connection that is not closed.
+    pub fn ignore_pings(&self, ignore: bool) {
+        self.state.lock().unwrap().ignore_pings = ignore;
+    }
+
+    /// Attaches the given warning to the next response.
+    pub fn warn_next(&self, warning: impl Into<String>) {
+        self.state.lock().unwrap().warnings.push(warning.into());
+    }
+
+    /// Fetches every request received so far, in order.
+    pub fn requests(&self) -> Vec<ClientMessage<Value>> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Fetches the frames the given user has successfully sent to their model
+    /// so far, e.g. via [`Lighthouse::put_model`](crate::Lighthouse::put_model).
+    pub fn frames(&self, username: &str) -> Vec<Frame> {
+        let path = user_path(username, "model");
+        self.state.lock().unwrap().puts.iter()
+            .filter(|request| request.path == path)
+            .filter_map(|request| match from_value(request.payload.clone()) {
+                Ok(Model::Frame(frame)) => Some(frame),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Fetches the number of open connections.
+    pub fn connection_count(&self) -> usize {
+        self.state.lock().unwrap().connections.len()
+    }
+
+    /// Handles a message sent by the client on the given connection.
+    fn receive(&self, connection: usize, message: Message) {
+        let mut state = self.state.lock().unwrap();
+        match message {
+            Message::Binary(bytes) => match rmp_serde::from_slice::<ClientMessage<Value>>(&bytes) {
+                Ok(request) => {
+                    debug! { request_id = %request.request_id, verb = ?request.verb, path = ?request.path, "Mock received request" };
+                    state.requests.push(request.clone());
+                    if state.ignored > 0 {
+                        state.ignored -= 1;
+                        return;
+                    }
+                    let (code, payload) = match state.failures.pop_front() {
+                        Some(code) => (code.code(), Value::Nil),
+                        None => state.handle(connection, &request),
+                    };
+                    if request.verb == Verb::Put && (200..300).contains(&code) {
+                        state.puts.push(request.clone());
+                    }
+                    let warnings = mem::take(&mut state.warnings);
+                    state.send(connection, Some(request.request_id), code, request.meta, warnings, payload);
+                },
+                Err(error) => warn! { %error, "Mock received undecodable message" },
+            },
+            Message::Ping(payload) if !state.ignore_pings => state.send_raw(connection, Message::Pong(payload)),
+            Message::Close(_) => state.disconnect(connection),
+            _ => {},
+        }
+    }
+}
+
+impl State {
+    /// Handles the given request and produces the response.
+    fn handle(&mut self, connection: usize, request: &ClientMessage<Value>) -> Response {
+        let path = &request.path;
+        let payload = request.payload.clone();
+        match &request.verb {
+            Verb::Get => match self.node(path) {
+                Some(Node::Resource(value)) => (200, value.clone()),
+                Some(Node::Directory(_)) => (400, Value::Nil),
+                None => (404, Value::Nil),
+            },
+            Verb::Put => match self.node(path) {
+                Some(Node::Resource(_)) => {
+                    self.update(path, payload);
+                    (200, Value::Nil)
+                },
+                Some(Node::Directory(_)) => (400, Value::Nil),
+                None => (404, Value::Nil),
+            },
+            Verb::Post => match self.node(path) {
+                Some(Node::Directory(_)) => (400, Value::Nil),
+                Some(Node::Resource(_)) => {
+                    self.update(path, payload);
+                    (200, Value::Nil)
+                },
+                None => self.create(path, Node::Resource(Value::Nil)).map_or_else(|code| (code, Value::Nil), |()| {
+                    self.update(path, payload);
+                    (201, Value::Nil)
+                }),
+            },
+            Verb::Create => self.create(path, Node::Resource(Value::Nil)).map_or_else(|code| (code, Value::Nil), |()| (201, Value::Nil)),
+            Verb::Mkdir => self.create(path, Node::Directory(BTreeMap::new())).map_or_else(|code| (code, Value::Nil), |()| (201, Value::Nil)),
+            Verb::Delete => match self.remove(path) {
+                Some(_) => (200, Value::Nil),
+                None => (404, Value::Nil),
+            },
+            Verb::List => match self.node(path) {
+                Some(Node::Directory(children)) => (200, to_value(directory_tree(children)).unwrap()),
+                Some(Node::Resource(_)) => (400, Value::Nil),
+                None => (404, Value::Nil),
+            },
+            Verb::Stream => match self.node(path) {
+                Some(Node::Resource(value)) => {
+                    let value = value.clone();
+                    self.subscriptions.push(Subscription { connection, request_id: request.request_id, path: path.clone() });
+                    (200, value)
+                },
+                Some(Node::Directory(_)) => (400, Value::Nil),
+                None => (404, Value::Nil),
+            },
+            Verb::Stop => {
+                let count = self.subscriptions.len();
+                self.subscriptions.retain(|s| !(s.connection == connection && s.request_id == request.request_id));
+                if self.subscriptions.len() < count { (200, Value::Nil) } else { (404, Value::Nil) }
+            },
+            Verb::Link | Verb::Unlink => {
+                let Ok(source) = from_value::<Vec<String>>(payload) else { return (400, Value::Nil) };
+                if !matches!(self.node(&source), Some(Node::Resource(_))) || !matches!(self.node(path), Some(Node::Resource(_))) {
+                    return (404, Value::Nil);
+                }
+                let destinations = self.links.entry(source).or_default();
+                let linked = destinations.contains(path);
+                if request.verb == Verb::Link && !linked {
+                    destinations.push(path.clone());
+                } else if request.verb == Verb::Unlink && linked {
+                    destinations.retain(|destination| destination != path);
+                } else {
+                    return (409, Value::Nil);
+                }
+                (200, Value::Nil)
+            },
+            Verb::Unknown(_) => (400, Value::Nil),
+        }
+    }
+
+    /// Looks up the node at the given path.
+    fn node(&self, path: &[String]) -> Option<&Node> {
+        let (name, parent) = path.split_last()?;
+        self.directory(parent)?.get(name)
+    }
+
+    /// Looks up the directory at the given path.
+    fn directory(&self, path: &[String]) -> Option<&BTreeMap<String, Node>> {
+        path.iter().try_fold(&self.root, |directory, name| match directory.get(name)? {
+            Node::Directory(children) => Some(children),
+            Node::Resource(_) => None,
+        })
+    }
+
+    /// Looks up the directory at the given path mutably.
+    fn directory_mut(&mut self, path: &[String]) -> Option<&mut BTreeMap<String, Node>> {
+        path.iter().try_fold(&mut self.root, |directory, name| match directory.get_mut(name)? {
+            Node::Directory(children) => Some(children),
+            Node::Resource(_) => None,
+        })
+    }
+
+    /// Creates the given node, failing with a status code if the parent
+    /// directory is missing or the node already exists.
+    fn create(&mut self, path: &[String], node: Node) -> Result<(), i32> {
+        let (name, parent) = path.split_last().ok_or(400)?;
+        let directory = self.directory_mut(parent).ok_or(404)?;
+        if directory.contains_key(name) {
+            return Err(409);
+        }
+        directory.insert(name.clone(), node);
+        Ok(())
+    }
+
+    /// Removes the node at the given path.
+    fn remove(&mut self, path: &[String]) -> Option<Node> {
+        let (name, parent) = path.split_last()?;
+        self.directory_mut(parent)?.remove(name)
+    }
+
+    /// Inserts a resource at the given path, creating parent directories.
+    fn insert(&mut self, path: &[String], value: Value) {
+        let Some((name, parent)) = path.split_last() else { return };
+        let mut directory = &mut self.root;
+        for segment in parent {
+            let node = directory.entry(segment.clone()).or_insert_with(|| Node::Directory(BTreeMap::new()));
+            if let Node::Resource(_) = node {
+                *node = Node::Directory(BTreeMap::new());
+            }
+            let Node::Directory(children) = node else { unreachable!() };
+            directory = children;
+        }
+        directory.insert(name.clone(), Node::Resource(value));
+    }
+
+    /// Sets the resource at the given path (and the resources linked to it),
+    /// notifying streams.
+    fn update(&mut self, path: &[String], value: Value) {
+        let mut visited = HashSet::new();
+        let mut pending = vec![path.to_vec()];
+        while let Some(path) = pending.pop() {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            self.insert(&path, value.clone());
+            let subscribers = self.subscriptions.iter()
+                .filter(|s| s.path == path)
+                .map(|s| (s.connection, s.request_id))
+                .collect::<Vec<_>>();
+            for (connection, request_id) in subscribers {
+                self.send(connection, Some(request_id), 200, HashMap::new(), Vec::new(), value.clone());
+            }
+            pending.extend(self.links.get(&path).into_iter().flatten().cloned());
+        }
+    }
+
+    /// Sends a response (or a notification, if there is no request id) to
+    /// the given connection.
+    fn send(&mut self, connection: usize, request_id: Option<i32>, code: impl Into<StatusCode>, meta: HashMap<String, String>, warnings: Vec<String>, payload: Value) {
+        let message = ServerMessage {
+            code: code.into(),
+            request_id,
+            warnings,
+            response: None,
+            meta,
+            payload,
+        };
+        let bytes = rmp_serde::to_vec_named(&message).expect("Could not encode server message");
+        self.send_raw(connection, Message::Binary(bytes));
+    }
+
+    /// Sends a raw WebSocket message to the given connection.
+    fn send_raw(&mut self, connection: usize, message: Message) {
+        let delivered = self.connections.get(&connection).is_some_and(|tx| tx.unbounded_send(message).is_ok());
+        if !delivered {
+            self.disconnect(connection);
+        }
+    }
+
+    /// Forgets the given connection and its streams.
+    fn disconnect(&mut self, connection: usize) {
+        self.connections.remove(&connection);
+        self.subscriptions.retain(|s| s.connection != connection);
+    }
+}
+
+/// Builds the path to the given resource of the given user.
+fn user_path(username: &str, resource: &str) -> Vec<String> {
+    vec!["user".to_owned(), username.to_owned(), resource.to_owned()]
+}
+
+/// Converts the given directory into the payload of a LIST response.
+fn directory_tree(children: &BTreeMap<String, Node>) -> DirectoryTree {
+    DirectoryTree {
+        entries: children.iter().map(|(name, node)| (name.clone(), match node {
+            Node::Resource(_) => None,
+            Node::Directory(children) => Some(directory_tree(children)),
+        })).collect(),
+    }
+}
+
+/// A client's in-memory connection to a [`MockLighthouse`], usable in place
+/// of a WebSocket.
+pub struct MockWebSocket {
+    server: MockLighthouse,
+    connection: usize,
+    incoming: UnboundedReceiver<Message>,
+    closed: bool,
+}
+
+impl Stream for MockWebSocket {
+    type Item = tungstenite::Result<Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.incoming).poll_next(cx).map(|message| message.map(Ok))
+    }
+}
+
+impl Sink<Message> for MockWebSocket {
+    type Error = tungstenite::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        Poll::Ready(self.check_open())
+    }
+
+    fn start_send(self: Pin<&mut Self>, message: Message) -> tungstenite::Result<()> {
+        self.check_open()?;
+        self.server.receive(self.connection, message);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        if !self.closed {
+            self.closed = true;
+            self.server.state.lock().unwrap().disconnect(self.connection);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl MockWebSocket {
+    /// Fails if the connection was closed by either side. The error type is
+    /// dictated by the `Sink` implementation.
+    #[allow(clippy::result_large_err)]
+    fn check_open(&self) -> tungstenite::Result<()> {
+        if self.closed {
+            Err(tungstenite::Error::AlreadyClosed)
+        } else if !self.server.state.lock().unwrap().connections.contains_key(&self.connection) {
+            Err(tungstenite::Error::ConnectionClosed)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Drop for MockWebSocket {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.server.state.lock() {
+            state.disconnect(self.connection);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::{collections::HashMap, time::Duration};
+
+use crate::RetryPolicy;
+
+/// Options for a single request. Unset options fall back to the defaults
+/// configured on the [`Lighthouse`](crate::Lighthouse).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestOptions {
+    /// The time to wait for a response before failing with
+    /// [`Error::Timeout`](crate::Error::Timeout).
+    pub timeout: Option<Duration>,
+    /// The number of messages buffered for a stream before the overflow
+    /// policy applies.
+    pub buffer: Option<usize>,
+    /// The policy to apply once a stream's buffer is full.
+    pub overflow: Option<OverflowPolicy>,
+    /// The META key/values sent along with the request. These are merged
+    /// with the default META, taking precedence over it.
+    pub meta: HashMap<String, String>,
+    /// The policy for retrying failed requests, `None` disables retrying.
+    pub retry: Option<RetryPolicy>,
+}
+
+/// What to do with messages for a stream whose consumer cannot keep up, i.e.
+/// whose buffer is full. Regardless of the policy, a slow consumer never
+/// holds up other requests or streams on the same connection.
+///
+/// There is deliberately no policy blocking until the consumer catches up:
+/// all streams share a single connection, so the server cannot be slowed
+/// down for one stream only. Such a policy would either stall every other
+/// stream or buffer without bound, i.e. behave like [`Unbounded`](Self::Unbounded).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Drops the oldest buffered message to make room for the new one. This
+    /// is the default, except for input streams.
+    #[default]
+    DropOldest,
+    /// Keeps buffering messages beyond the buffer size, i.e. never drops
+    /// messages, at the cost of unbounded memory usage if the consumer stalls.
+    /// This is the default for input streams, see
+    /// [`Lighthouse::stream_input_with_gaps`](crate::Lighthouse::stream_input_with_gaps).
+    Unbounded,
+    /// Drops the new message.
+    DropNewest,
+    /// Fails the stream with [`Error::BufferOverflow`](crate::Error::BufferOverflow).
+    Error,
+}
+
+impl RequestOptions {
+    /// Creates a new `RequestOptions` without any options set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the timeout for the request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the number of messages buffered for a stream.
+    pub fn with_buffer(mut self, buffer: usize) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    /// Sets the policy to apply once a stream's buffer is full.
+    pub fn with_overflow(mut self, overflow: OverflowPolicy) -> Self {
+        self.overflow = Some(overflow);
+        self
+    }
+
+    /// Adds a META key/value to send along with the request.
+    pub fn with_meta(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.meta.insert(key.into(), value.into());
+        self
+    }
+
+    /// Sets the policy for retrying the request if it fails.
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Fills the unset options from the given defaults.
+    pub(crate) fn or(&self, defaults: &RequestOptions) -> RequestOptions {
+        RequestOptions {
+            timeout: self.timeout.or(defaults.timeout),
+            buffer: self.buffer.or(defaults.buffer),
+            overflow: self.overflow.or(defaults.overflow),
+            meta: defaults.meta.iter().chain(&self.meta).map(|(k, v)| (k.clone(), v.clone())).collect(),
+            retry: self.retry.clone().or_else(|| defaults.retry.clone()),
+        }
+    }
+}
+
+/// Limits for messages whose request id has no registered slot (yet), e.g.
+/// late responses to timed out requests or stray messages after a STOP.
+/// These are retained for a while, since the corresponding request may still
+/// be about to register its slot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EarlyMessageLimits {
+    /// The maximum number of messages retained per request id.
+    pub per_request: usize,
+    /// The maximum number of messages retained across all request ids.
+    pub total: usize,
+    /// The duration after which retained messages are discarded.
+    pub expiry: Duration,
+}
+
+impl Default for EarlyMessageLimits {
+    fn default() -> Self {
+        Self {
+            per_request: 16,
+            total: 256,
+            expiry: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Configures the WebSocket pings used to detect dead connections and to
+/// measure the round-trip latency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Keepalive {
+    /// The interval at which pings are sent.
+    pub interval: Duration,
+    /// The time to wait for a pong before considering the connection dead.
+    pub timeout: Duration,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(15),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
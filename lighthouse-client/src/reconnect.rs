@@ -0,0 +1,93 @@
+use std::time::Duration;
+
+use lighthouse_protocol::{ServerMessage, Value, ValueError};
+use serde::Deserialize;
+
+/// A policy describing how a [`Lighthouse`](crate::Lighthouse) reconnects
+/// after the connection drops, using exponential backoff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconnectPolicy {
+    /// The delay before the first reconnect attempt.
+    pub initial_delay: Duration,
+    /// The upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// The factor by which the delay grows after every failed attempt.
+    pub multiplier: f64,
+    /// The maximum number of consecutive attempts, `None` retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl ReconnectPolicy {
+    /// Computes the delay before the given (zero-based) attempt.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            max_attempts: None,
+        }
+    }
+}
+
+/// An item of a stream that survives reconnects.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamEvent<P> {
+    /// A message received from the server.
+    Message(ServerMessage<P>),
+    /// A marker indicating that the connection dropped and was re-established.
+    /// Any messages sent by the server in the meantime were missed.
+    Gap,
+}
+
+impl<P> StreamEvent<P> {
+    /// Fetches the message, if this is not a gap.
+    pub fn into_message(self) -> Option<ServerMessage<P>> {
+        match self {
+            Self::Message(message) => Some(message),
+            Self::Gap => None,
+        }
+    }
+}
+
+impl StreamEvent<Value> {
+    /// Decodes the payload of the message, if this is not a gap.
+    pub fn decode_payload<R>(self) -> Result<StreamEvent<R>, ValueError>
+    where
+        R: for<'de> Deserialize<'de> {
+        Ok(match self {
+            Self::Message(message) => StreamEvent::Message(message.decode_payload()?),
+            Self::Gap => StreamEvent::Gap,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::ReconnectPolicy;
+
+    #[test]
+    fn exponential_backoff() {
+        let policy = ReconnectPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            multiplier: 2.0,
+            max_attempts: None,
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(4), Duration::from_secs(1));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(1));
+    }
+}
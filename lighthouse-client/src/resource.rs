@@ -0,0 +1,113 @@
+use std::{fmt, marker::PhantomData};
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{Sink, Stream};
+use lighthouse_protocol::{ResourcePath, ServerMessage};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Lighthouse, RequestOptions, Result, StreamEvent};
+
+/// A handle to the resource at a fixed path with a fixed payload type,
+/// obtained via [`Lighthouse::resource`]. Handles are cheap to clone.
+///
+/// ```no_run
+/// # use lighthouse_client::{Lighthouse, TokioWebSocket};
+/// # async fn run(lh: Lighthouse<TokioWebSocket>) -> lighthouse_client::Result<()> {
+/// let greeting = lh.resource::<String>("/test/greeting")?;
+/// greeting.put("Hello world!".to_owned()).await?;
+/// let greeting: String = greeting.get().await?.payload;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Resource<S, T> {
+    lh: Lighthouse<S>,
+    path: ResourcePath,
+    payload: PhantomData<fn(T) -> T>,
+}
+
+impl<S, T> Resource<S, T> {
+    /// Creates a new handle to the resource at the given path.
+    pub(crate) fn new(lh: Lighthouse<S>, path: ResourcePath) -> Self {
+        Self { lh, path, payload: PhantomData }
+    }
+
+    /// The path of the resource.
+    pub fn path(&self) -> &ResourcePath {
+        &self.path
+    }
+}
+
+impl<S, T> Resource<S, T>
+    where S: Stream<Item = tungstenite::Result<Message>>
+           + Sink<Message, Error = tungstenite::Error>
+           + Send
+           + 'static,
+          T: Serialize + for<'de> Deserialize<'de> {
+    /// Gets the resource. Requires READ permission.
+    pub async fn get(&self) -> Result<ServerMessage<T>> {
+        self.lh.get(&self.path).await
+    }
+
+    /// Updates the resource with the given payload. Requires WRITE permission.
+    pub async fn put(&self, payload: T) -> Result<ServerMessage<()>> {
+        self.lh.put(&self.path, payload).await
+    }
+
+    /// Updates the resource with the given payload without waiting for the
+    /// server's response, see [`Lighthouse::perform_nowait`].
+    pub async fn put_nowait(&self, payload: T) -> Result<()> {
+        self.lh.put_nowait(&self.path, payload).await
+    }
+
+    /// Combines PUT and CREATE. Requires CREATE and WRITE permission.
+    pub async fn post(&self, payload: T) -> Result<ServerMessage<()>> {
+        self.lh.post(&self.path, payload).await
+    }
+
+    /// Creates the resource. Requires CREATE permission.
+    pub async fn create(&self) -> Result<ServerMessage<()>> {
+        self.lh.create(&self.path).await
+    }
+
+    /// Deletes the resource. Requires DELETE permission.
+    pub async fn delete(&self) -> Result<ServerMessage<()>> {
+        self.lh.delete(&self.path).await
+    }
+
+    /// Streams the resource. Automatically sends a STOP once dropped.
+    pub async fn stream(&self) -> Result<impl Stream<Item = Result<ServerMessage<T>>>> {
+        self.lh.stream(self.path.clone(), ()).await
+    }
+
+    /// Streams the resource with the given options, yielding a
+    /// [`StreamEvent::Gap`] whenever the connection was re-established.
+    pub async fn stream_with_gaps(&self, options: &RequestOptions) -> Result<impl Stream<Item = Result<StreamEvent<T>>>> {
+        self.lh.stream_with_gaps(self.path.clone(), (), options).await
+    }
+
+    /// Links this resource to the given destination, i.e. forwards its
+    /// updates to the destination.
+    pub async fn link(&self, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.lh.link(&self.path, dest_path).await
+    }
+
+    /// Unlinks this resource from the given destination.
+    pub async fn unlink(&self, dest_path: impl TryInto<ResourcePath, Error: Into<Error>>) -> Result<ServerMessage<()>> {
+        self.lh.unlink(&self.path, dest_path).await
+    }
+}
+
+impl<S, T> Clone for Resource<S, T> {
+    fn clone(&self) -> Self {
+        Self::new(self.lh.clone(), self.path.clone())
+    }
+}
+
+impl<S, T> fmt::Debug for Resource<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Resource")
+            .field("path", &self.path)
+            .field("payload", &std::any::type_name::<T>())
+            .finish()
+    }
+}
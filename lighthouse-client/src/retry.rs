@@ -0,0 +1,129 @@
+use std::{collections::HashSet, time::Duration};
+
+use lighthouse_protocol::{StatusCode, Verb};
+
+/// A policy describing which failed requests are retried and how long to
+/// wait between attempts, using exponential backoff with jitter.
+///
+/// Only requests failing with one of the given status codes are retried,
+/// i.e. timeouts and connection errors are not. By default, only idempotent
+/// verbs are retried. Retrying is opt-in, see
+/// [`RequestOptions::with_retry`](crate::RequestOptions::with_retry) and
+/// [`LighthouseBuilder::retry`](crate::LighthouseBuilder::retry).
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// The verbs whose requests are retried.
+    pub verbs: HashSet<Verb>,
+    /// The status codes upon which requests are retried.
+    pub codes: HashSet<StatusCode>,
+    /// The maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// The delay before the first retry.
+    pub initial_delay: Duration,
+    /// The upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// The factor by which the delay grows after every failed attempt.
+    pub multiplier: f64,
+    /// The fraction (between 0 and 1) by which delays are randomly varied
+    /// in either direction, so clients do not retry in lockstep.
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    /// Sets the verbs whose requests are retried.
+    pub fn with_verbs(mut self, verbs: impl IntoIterator<Item = Verb>) -> Self {
+        self.verbs = verbs.into_iter().collect();
+        self
+    }
+
+    /// Sets the status codes upon which requests are retried.
+    pub fn with_codes(mut self, codes: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.codes = codes.into_iter().collect();
+        self
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Whether a request with the given verb failing with the given status
+    /// code after the given number of attempts is retried.
+    pub fn should_retry(&self, verb: &Verb, code: StatusCode, attempts: u32) -> bool {
+        attempts < self.max_attempts && self.verbs.contains(verb) && self.codes.contains(&code)
+    }
+
+    /// Computes the delay before the given (one-based) retry without jitter.
+    pub fn base_delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1).min(i32::MAX as u32) as i32);
+        Duration::try_from_secs_f64(self.initial_delay.as_secs_f64() * factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+
+    /// Computes the delay before the given (one-based) retry, randomly
+    /// varied according to the jitter.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 + jitter * (2.0 * rand::random::<f64>() - 1.0);
+        Duration::try_from_secs_f64(self.base_delay(retry).as_secs_f64() * factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            verbs: HashSet::from([Verb::Get, Verb::Put, Verb::List, Verb::Delete]),
+            codes: HashSet::from([
+                StatusCode::RequestTimeout,
+                StatusCode::TooManyRequests,
+                StatusCode::BadGateway,
+                StatusCode::ServiceUnavailable,
+                StatusCode::GatewayTimeout,
+            ]),
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use lighthouse_protocol::{StatusCode, Verb};
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn retries_idempotent_verbs_with_backoff() {
+        let policy = RetryPolicy::default();
+        assert!(policy.should_retry(&Verb::Put, StatusCode::ServiceUnavailable, 1));
+        assert!(!policy.should_retry(&Verb::Put, StatusCode::ServiceUnavailable, 3));
+        assert!(!policy.should_retry(&Verb::Put, StatusCode::NotFound, 1));
+        assert!(!policy.should_retry(&Verb::Post, StatusCode::ServiceUnavailable, 1));
+        assert_eq!(policy.base_delay(1), Duration::from_millis(100));
+        assert_eq!(policy.base_delay(3), Duration::from_millis(400));
+        assert_eq!(policy.base_delay(u32::MAX), Duration::from_secs(5));
+        for _ in 0..100 {
+            let delay = policy.delay(2);
+            assert!(delay >= Duration::from_millis(159) && delay <= Duration::from_millis(241));
+        }
+    }
+
+    #[test]
+    fn caps_delays_that_do_not_fit() {
+        let policy = RetryPolicy { max_delay: Duration::MAX, jitter: 0.5, ..Default::default() };
+        for _ in 0..100 {
+            assert!(policy.delay(u32::MAX) >= Duration::MAX / 4);
+        }
+        let policy = RetryPolicy { jitter: f64::NAN, ..Default::default() };
+        assert_eq!(policy.delay(1), Duration::from_secs(5));
+    }
+}
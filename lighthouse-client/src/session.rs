@@ -0,0 +1,255 @@
+use std::{collections::VecDeque, fmt, fs::File, io::{self, BufReader, BufWriter, ErrorKind, Read, Write}, path::Path, pin::Pin, sync::{Arc, Mutex}, task::{Context, Poll, Waker}, time::{Duration, Instant}};
+
+use async_tungstenite::tungstenite::{self, Message};
+use futures::{Sink, Stream};
+use lighthouse_protocol::{ClientMessage, ServerMessage, Value};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// The token recorded in place of the actual one.
+const REDACTED_TOKEN: &str = "<redacted>";
+
+/// A recording of the messages exchanged over a connection, e.g. to attach a
+/// reproducible trace to a bug report or to run regression tests against
+/// real traffic via [`Session::replay`].
+///
+/// Sessions are stored as a sequence of MessagePack-encoded
+/// [`SessionEntry`]s and can be recorded with a [`SessionRecorder`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Session {
+    /// The recorded entries in chronological order.
+    pub entries: Vec<SessionEntry>,
+}
+
+/// A single message of a recorded [`Session`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionEntry {
+    /// The time elapsed since the recording started.
+    pub time: Duration,
+    /// The recorded message.
+    pub event: SessionEvent,
+}
+
+/// A message sent or received over a connection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SessionEvent {
+    /// A message sent by the client. The token is redacted.
+    Sent(ClientMessage<Value>),
+    /// A message received from the server.
+    Received(ServerMessage<Value>),
+}
+
+impl Session {
+    /// Reads a session from the given file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads a session from the given reader until it ends.
+    pub fn read_from(mut reader: impl Read) -> io::Result<Self> {
+        let mut entries = Vec::new();
+        loop {
+            match rmp_serde::decode::from_read(&mut reader) {
+                Ok(entry) => entries.push(entry),
+                Err(rmp_serde::decode::Error::InvalidMarkerRead(e)) if e.kind() == ErrorKind::UnexpectedEof => break,
+                Err(e) => return Err(io::Error::new(ErrorKind::InvalidData, e)),
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    /// Writes the session to the given file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Writes the session to the given writer.
+    pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+        for entry in &self.entries {
+            write_entry(&mut writer, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a fake WebSocket playing back the messages received in this
+    /// session, see [`ReplayWebSocket`].
+    pub fn replay(&self) -> ReplayWebSocket {
+        ReplayWebSocket::new(self.entries.iter().cloned().collect())
+    }
+}
+
+/// Writes a single entry to the given writer.
+fn write_entry(writer: &mut impl Write, entry: &SessionEntry) -> io::Result<()> {
+    rmp_serde::encode::write_named(writer, entry).map_err(|e| io::Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Records the messages exchanged over a connection to a writer, e.g. a
+/// file, in the format read by [`Session::load`]. Tokens are redacted.
+///
+/// Recorders are attached via
+/// [`LighthouseBuilder::record`](crate::LighthouseBuilder::record) or
+/// [`Lighthouse::set_recorder`](crate::Lighthouse::set_recorder) and keep
+/// recording across reconnects. Failures to write are logged and do not
+/// affect the connection.
+#[derive(Clone)]
+pub struct SessionRecorder {
+    /// The time at which the recording started.
+    start: Instant,
+    /// The writer the entries are written to.
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+}
+
+impl SessionRecorder {
+    /// Creates a recorder writing to the given writer.
+    pub fn new(writer: impl Write + Send + 'static) -> Self {
+        Self {
+            start: Instant::now(),
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+        }
+    }
+
+    /// Creates a recorder writing to the given file, replacing it if it
+    /// already exists.
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Records the given encoded message sent by the client.
+    pub(crate) fn record_sent(&self, bytes: &[u8]) {
+        match rmp_serde::from_slice::<ClientMessage<Value>>(bytes) {
+            Ok(mut message) => {
+                message.authentication.token = REDACTED_TOKEN.to_owned();
+                self.record(SessionEvent::Sent(message));
+            },
+            Err(error) => warn! { %error, "Could not record sent message" },
+        }
+    }
+
+    /// Records the given message received from the server.
+    pub(crate) fn record_received(&self, message: &ServerMessage<Value>) {
+        self.record(SessionEvent::Received(message.clone()));
+    }
+
+    fn record(&self, event: SessionEvent) {
+        let entry = SessionEntry { time: self.start.elapsed(), event };
+        let mut writer = self.writer.lock().unwrap();
+        if let Err(error) = write_entry(&mut *writer, &entry).and_then(|_| writer.flush()) {
+            warn! { %error, "Could not record message" };
+        }
+    }
+}
+
+impl fmt::Debug for SessionRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionRecorder")
+            .field("start", &self.start)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A fake WebSocket playing back the messages received in a recorded
+/// [`Session`], created via [`Session::replay`].
+///
+/// Every received message is delivered once the client has sent as many
+/// messages as preceded it in the recording, so responses do not overtake
+/// their requests. The client is expected to send the same requests (in
+/// particular, with the same request ids) as in the recording, which is
+/// the case for deterministic clients. Recorded timings are not reproduced,
+/// pings are answered. The stream ends once the recording is exhausted.
+pub struct ReplayWebSocket {
+    /// The entries that have not been played back yet.
+    entries: VecDeque<SessionEntry>,
+    /// The number of messages sent by the client that have not been matched
+    /// against a recorded message yet.
+    unmatched_sent: usize,
+    /// The pongs to send in response to pings.
+    pongs: VecDeque<Vec<u8>>,
+    /// Whether the client closed the connection.
+    closed: bool,
+    /// The waker of the task waiting for the next message.
+    waker: Option<Waker>,
+}
+
+impl ReplayWebSocket {
+    /// Creates a fake WebSocket playing back the given entries.
+    pub fn new(entries: VecDeque<SessionEntry>) -> Self {
+        Self {
+            entries,
+            unmatched_sent: 0,
+            pongs: VecDeque::new(),
+            closed: false,
+            waker: None,
+        }
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl Stream for ReplayWebSocket {
+    type Item = tungstenite::Result<Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if let Some(pong) = self.pongs.pop_front() {
+            return Poll::Ready(Some(Ok(Message::Pong(pong))));
+        }
+        if self.closed {
+            return Poll::Ready(None);
+        }
+        loop {
+            match self.entries.front().map(|entry| &entry.event) {
+                Some(SessionEvent::Sent(_)) if self.unmatched_sent > 0 => {
+                    self.unmatched_sent -= 1;
+                    self.entries.pop_front();
+                },
+                Some(SessionEvent::Sent(_)) => {
+                    self.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                },
+                Some(SessionEvent::Received(message)) => {
+                    let bytes = rmp_serde::to_vec_named(message).map_err(|e| tungstenite::Error::Io(io::Error::new(ErrorKind::InvalidData, e)));
+                    self.entries.pop_front();
+                    return Poll::Ready(Some(bytes.map(Message::Binary)));
+                },
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl Sink<Message> for ReplayWebSocket {
+    type Error = tungstenite::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        Poll::Ready(if self.closed { Err(tungstenite::Error::AlreadyClosed) } else { Ok(()) })
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, message: Message) -> tungstenite::Result<()> {
+        if self.closed {
+            return Err(tungstenite::Error::AlreadyClosed);
+        }
+        match message {
+            Message::Binary(_) => self.unmatched_sent += 1,
+            Message::Ping(payload) => self.pongs.push_back(payload),
+            Message::Close(_) => self.closed = true,
+            _ => {},
+        }
+        self.wake();
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<tungstenite::Result<()>> {
+        self.closed = true;
+        self.wake();
+        Poll::Ready(Ok(()))
+    }
+}
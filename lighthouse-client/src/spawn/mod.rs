@@ -3,7 +3,7 @@ mod async_std;
 #[cfg(feature = "tokio")]
 mod tokio;
 
-use futures::Future;
+use futures::{future::BoxFuture, Future, FutureExt};
 
 #[cfg(feature = "async-std")]
 pub use self::async_std::*;
@@ -14,3 +14,16 @@ pub use self::tokio::*;
 pub trait Spawner {
     fn spawn<F>(future: F) where F: Future + Send + 'static, F::Output: Send;
 }
+
+/// A type-erased [`Spawner`], which can be stored without a type parameter.
+pub(crate) type SpawnFn = fn(BoxFuture<'static, ()>);
+
+/// Erases the type of the given [`Spawner`].
+pub(crate) fn spawn_fn<W>() -> SpawnFn where W: Spawner {
+    |future| W::spawn(future)
+}
+
+/// Spawns the given future using the given type-erased spawner.
+pub(crate) fn spawn_with(spawn: SpawnFn, future: impl Future<Output = ()> + Send + 'static) {
+    spawn(future.boxed())
+}
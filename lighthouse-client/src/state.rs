@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// The state of the connection to the lighthouse, as observed via
+/// [`Lighthouse::state`](crate::Lighthouse::state).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// A (re)connect attempt is in progress.
+    Connecting,
+    /// The connection is established.
+    Connected,
+    /// The connection dropped, e.g. because the server went away or did not
+    /// answer keepalive pings. Followed by [`ConnectionState::Connecting`] if
+    /// the connection reconnects, otherwise by [`ConnectionState::Closed`].
+    Disconnected {
+        /// A human-readable description of why the connection dropped.
+        reason: String,
+    },
+    /// The connection was closed for good, either deliberately or because
+    /// reconnecting was not configured or gave up.
+    Closed,
+}
+
+impl ConnectionState {
+    /// Whether requests can currently be sent.
+    pub fn is_connected(&self) -> bool {
+        matches!(self, Self::Connected)
+    }
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connecting => write!(f, "connecting"),
+            Self::Connected => write!(f, "connected"),
+            Self::Disconnected { reason } => write!(f, "disconnected ({reason})"),
+            Self::Closed => write!(f, "closed"),
+        }
+    }
+}
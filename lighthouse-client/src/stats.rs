@@ -0,0 +1,253 @@
+use std::{collections::{HashMap, VecDeque}, sync::Mutex, time::{Duration, Instant}};
+
+use lighthouse_protocol::Verb;
+
+/// The upper bounds of the latency histogram's buckets, in milliseconds.
+const BUCKET_BOUNDS_MS: [u64; 13] = [1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+/// The window over which the frame rate is measured.
+const FRAME_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// A snapshot of the statistics of a connection, see
+/// [`Lighthouse::stats`](crate::Lighthouse::stats).
+///
+/// With the `metrics` feature, the statistics are additionally exported
+/// through the [`metrics`](https://docs.rs/metrics) facade as the counters
+/// `lighthouse_requests_total` (labeled by `verb`),
+/// `lighthouse_bytes_sent_total`, `lighthouse_bytes_received_total`,
+/// `lighthouse_frames_total` and `lighthouse_decode_errors_total`, the
+/// histogram `lighthouse_put_latency_seconds` and the gauges
+/// `lighthouse_streams` and `lighthouse_stream_subscribers`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Stats {
+    /// The number of requests sent, per verb, including resubscriptions.
+    pub requests: HashMap<Verb, u64>,
+    /// The number of bytes sent in binary messages.
+    pub bytes_sent: u64,
+    /// The number of bytes received in binary messages.
+    pub bytes_received: u64,
+    /// The round-trip latencies of PUT requests that were answered.
+    pub put_latency: Histogram,
+    /// The number of frames sent to the user's model via
+    /// [`Lighthouse::put_model`](crate::Lighthouse::put_model) and friends.
+    pub frames: u64,
+    /// The number of frames sent during the last second.
+    pub frames_per_second: f64,
+    /// The number of received messages that could not be decoded.
+    pub decode_errors: u64,
+    /// The number of active STREAM subscriptions on the server.
+    pub streams: usize,
+    /// The number of local streams consuming these subscriptions.
+    pub stream_subscribers: usize,
+    /// The number of requests currently awaiting their response, see
+    /// [`LighthouseBuilder::max_in_flight`](crate::LighthouseBuilder::max_in_flight).
+    pub in_flight: usize,
+}
+
+/// A histogram of durations with exponentially growing buckets, ranging
+/// from 1 ms to about 4 s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The number of samples per bucket, the last one being unbounded.
+    counts: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    /// The total of all samples.
+    sum: Duration,
+    /// The smallest sample.
+    min: Option<Duration>,
+    /// The largest sample.
+    max: Option<Duration>,
+}
+
+impl Histogram {
+    /// Records the given sample.
+    pub(crate) fn record(&mut self, sample: Duration) {
+        let bucket = BUCKET_BOUNDS_MS.iter()
+            .position(|&bound| sample <= Duration::from_millis(bound))
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.counts[bucket] += 1;
+        self.sum += sample;
+        self.min = Some(self.min.map_or(sample, |min| min.min(sample)));
+        self.max = Some(self.max.map_or(sample, |max| max.max(sample)));
+    }
+
+    /// The number of samples.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// The total of all samples.
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    /// The smallest sample, if any.
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// The largest sample, if any.
+    pub fn max(&self) -> Option<Duration> {
+        self.max
+    }
+
+    /// The average of all samples, if any.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count()).ok().filter(|&count| count > 0)?;
+        Some(self.sum / count)
+    }
+
+    /// Estimates the given quantile (between 0 and 1), e.g. 0.99 for the
+    /// 99th percentile, as the upper bound of the bucket containing it.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, bucket_count) in self.counts.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= rank {
+                let bound = BUCKET_BOUNDS_MS.get(bucket).map(|&bound| Duration::from_millis(bound));
+                return bound.into_iter().chain(self.max).min();
+            }
+        }
+        self.max
+    }
+
+    /// The buckets as pairs of their upper bound (`None` for the last,
+    /// unbounded one) and the number of samples in them.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        BUCKET_BOUNDS_MS.iter()
+            .map(|&bound| Some(Duration::from_millis(bound)))
+            .chain([None])
+            .zip(self.counts.iter().copied())
+    }
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            counts: [0; BUCKET_BOUNDS_MS.len() + 1],
+            sum: Duration::ZERO,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+/// Collects the statistics of a connection.
+#[derive(Debug, Default)]
+pub(crate) struct Telemetry {
+    state: Mutex<TelemetryState>,
+}
+
+#[derive(Debug, Default)]
+struct TelemetryState {
+    stats: Stats,
+    /// The times at which the frames within the frame rate window were sent.
+    frame_times: VecDeque<Instant>,
+}
+
+impl Telemetry {
+    /// Records a request with the given verb.
+    pub(crate) fn record_request(&self, verb: &Verb) {
+        *self.state.lock().unwrap().stats.requests.entry(verb.clone()).or_default() += 1;
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lighthouse_requests_total", "verb" => verb.to_string()).increment(1);
+    }
+
+    /// Records a sent binary message of the given size.
+    pub(crate) fn record_sent(&self, bytes: usize) {
+        self.state.lock().unwrap().stats.bytes_sent += bytes as u64;
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lighthouse_bytes_sent_total").increment(bytes as u64);
+    }
+
+    /// Records a received binary message of the given size.
+    pub(crate) fn record_received(&self, bytes: usize) {
+        self.state.lock().unwrap().stats.bytes_received += bytes as u64;
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lighthouse_bytes_received_total").increment(bytes as u64);
+    }
+
+    /// Records the round-trip latency of a PUT request.
+    pub(crate) fn record_put_latency(&self, latency: Duration) {
+        self.state.lock().unwrap().stats.put_latency.record(latency);
+        #[cfg(feature = "metrics")]
+        metrics::histogram!("lighthouse_put_latency_seconds").record(latency);
+    }
+
+    /// Records a frame sent to the user's model.
+    pub(crate) fn record_frame(&self) {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        state.stats.frames += 1;
+        state.frame_times.push_back(now);
+        prune_frame_times(&mut state.frame_times, now);
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lighthouse_frames_total").increment(1);
+    }
+
+    /// Records a received message that could not be decoded.
+    pub(crate) fn record_decode_error(&self) {
+        self.state.lock().unwrap().stats.decode_errors += 1;
+        #[cfg(feature = "metrics")]
+        metrics::counter!("lighthouse_decode_errors_total").increment(1);
+    }
+
+    /// Records the number of active STREAM subscriptions.
+    pub(crate) fn set_streams(&self, streams: usize) {
+        self.state.lock().unwrap().stats.streams = streams;
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("lighthouse_streams").set(streams as f64);
+    }
+
+    /// Records that a local stream was created (`delta = 1`) or dropped
+    /// (`delta = -1`).
+    pub(crate) fn add_stream_subscribers(&self, delta: isize) {
+        let mut state = self.state.lock().unwrap();
+        state.stats.stream_subscribers = state.stats.stream_subscribers.saturating_add_signed(delta);
+        #[cfg(feature = "metrics")]
+        metrics::gauge!("lighthouse_stream_subscribers").set(state.stats.stream_subscribers as f64);
+    }
+
+    /// Takes a snapshot of the statistics.
+    pub(crate) fn snapshot(&self) -> Stats {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        prune_frame_times(&mut state.frame_times, now);
+        let frames_per_second = state.frame_times.len() as f64 / FRAME_RATE_WINDOW.as_secs_f64();
+        Stats { frames_per_second, ..state.stats.clone() }
+    }
+}
+
+/// Forgets the frames that were sent before the frame rate window.
+fn prune_frame_times(frame_times: &mut VecDeque<Instant>, now: Instant) {
+    while frame_times.front().is_some_and(|&time| now.duration_since(time) > FRAME_RATE_WINDOW) {
+        frame_times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Histogram;
+
+    #[test]
+    fn histogram_quantiles() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.quantile(0.5), None);
+        for ms in [1, 3, 3, 10, 5000] {
+            histogram.record(Duration::from_millis(ms));
+        }
+        assert_eq!(histogram.count(), 5);
+        assert_eq!(histogram.mean(), Some(Duration::from_micros(1_003_400)));
+        assert_eq!(histogram.quantile(0.0), Some(Duration::from_millis(1)));
+        assert_eq!(histogram.quantile(0.5), Some(Duration::from_millis(4)));
+        assert_eq!(histogram.quantile(0.8), Some(Duration::from_millis(16)));
+        assert_eq!(histogram.quantile(1.0), Some(Duration::from_millis(5000)));
+        assert_eq!(histogram.buckets().last(), Some((None, 1)));
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Mutex;
+
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+/// A set of subscribers to which values are broadcast.
+pub(crate) struct Subscribers<T> {
+    senders: Mutex<Vec<UnboundedSender<T>>>,
+}
+
+impl<T> Subscribers<T> where T: Clone {
+    /// Registers a new subscriber.
+    pub(crate) fn subscribe(&self) -> UnboundedReceiver<T> {
+        let (tx, rx) = mpsc::unbounded();
+        self.senders.lock().unwrap().push(tx);
+        rx
+    }
+
+    /// Sends the given value to all subscribers, forgetting those that have
+    /// been dropped.
+    pub(crate) fn broadcast(&self, value: T) {
+        self.senders.lock().unwrap().retain(|tx| tx.unbounded_send(value.clone()).is_ok());
+    }
+}
+
+impl<T> Default for Subscribers<T> {
+    fn default() -> Self {
+        Self { senders: Mutex::new(Vec::new()) }
+    }
+}
+
+/// A value whose changes are broadcast to subscribers, which first receive
+/// the current value. Once finished, the value does not change anymore and
+/// all subscriptions end.
+pub(crate) struct Watch<T> {
+    state: Mutex<WatchState<T>>,
+    subscribers: Subscribers<T>,
+}
+
+struct WatchState<T> {
+    value: T,
+    finished: bool,
+}
+
+impl<T> Watch<T> where T: Clone + PartialEq {
+    /// Creates a new watch holding the given value.
+    pub(crate) fn new(value: T) -> Self {
+        Self { state: Mutex::new(WatchState { value, finished: false }), subscribers: Subscribers::default() }
+    }
+
+    /// Fetches the current value.
+    pub(crate) fn get(&self) -> T {
+        self.state.lock().unwrap().value.clone()
+    }
+
+    /// Replaces the current value and broadcasts it if it changed, ending all
+    /// subscriptions afterwards if `finish` is set. Returns whether the value
+    /// changed.
+    pub(crate) fn set(&self, value: T, finish: bool) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.finished {
+            return false;
+        }
+        let changed = state.value != value;
+        if changed {
+            state.value = value.clone();
+            self.subscribers.broadcast(value);
+        }
+        if finish {
+            state.finished = true;
+            self.subscribers.senders.lock().unwrap().clear();
+        }
+        changed
+    }
+
+    /// Registers a new subscriber, which receives the current value first.
+    pub(crate) fn subscribe(&self) -> UnboundedReceiver<T> {
+        // Holding the lock ensures no change is missed or delivered twice
+        let state = self.state.lock().unwrap();
+        let (tx, rx) = mpsc::unbounded();
+        if tx.unbounded_send(state.value.clone()).is_ok() && !state.finished {
+            self.subscribers.senders.lock().unwrap().push(tx);
+        }
+        rx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, StreamExt};
+
+    use super::Watch;
+
+    #[test]
+    fn watch_replays_current_value_and_finishes() {
+        let watch = Watch::new(0);
+        assert!(watch.set(1, false));
+        let rx = watch.subscribe();
+        assert!(!watch.set(1, false));
+        assert!(watch.set(2, true));
+        assert!(!watch.set(3, false));
+        assert_eq!(block_on(rx.collect::<Vec<_>>()), vec![1, 2]);
+        assert_eq!(block_on(watch.subscribe().collect::<Vec<_>>()), vec![2]);
+    }
+}
@@ -0,0 +1,18 @@
+// Not every test uses every helper
+#![allow(dead_code)]
+
+use lighthouse_client::{protocol::Authentication, Lighthouse, LighthouseBuilder, MockLighthouse, MockWebSocket, TokioSpawner};
+
+/// Connects to the given mock server as alice.
+pub fn connect(mock: &MockLighthouse) -> Lighthouse<MockWebSocket> {
+    connect_with(mock, |builder| builder)
+}
+
+/// Connects to the given mock server as alice, configuring the builder via
+/// the given function first.
+pub fn connect_with(mock: &MockLighthouse, configure: impl FnOnce(LighthouseBuilder) -> LighthouseBuilder) -> Lighthouse<MockWebSocket> {
+    configure(LighthouseBuilder::new(Authentication::new("alice", "token")))
+        .spawner::<TokioSpawner>()
+        .build(mock.connect())
+        .unwrap()
+}
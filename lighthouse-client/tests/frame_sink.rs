@@ -0,0 +1,66 @@
+mod common;
+
+use std::time::Duration;
+
+use futures::{future, SinkExt};
+use lighthouse_client::{protocol::{Color, Frame, StatusCode}, Error, MockLighthouse};
+
+use common::{connect, connect_with};
+
+#[tokio::test]
+async fn sends_newest_frame() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    let mut sink = lh.frame_sink(None);
+    // The background sender does not run before the current task yields
+    for color in [Color::RED, Color::GREEN, Color::BLUE] {
+        sink.feed(Frame::fill(color)).await.unwrap();
+    }
+    sink.close().await.unwrap();
+    assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::BLUE)]);
+}
+
+#[tokio::test]
+async fn keeps_one_put_in_flight() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect_with(&mock, |builder| builder.timeout(Duration::from_millis(50)));
+    let mut sink = lh.frame_sink(None);
+    mock.ignore_requests(1);
+    sink.send(Frame::fill(Color::RED)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    sink.send(Frame::fill(Color::GREEN)).await.unwrap();
+    sink.send(Frame::fill(Color::BLUE)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(mock.requests().len(), 1);
+
+    // Closing waits for the newest frame and reports the timed out one
+    let error = sink.close().await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Timeout));
+    assert_eq!(mock.requests().len(), 2);
+    assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::BLUE)]);
+}
+
+#[tokio::test]
+async fn reports_server_errors() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    // Invalid rates leave the rate uncapped
+    let mut sink = lh.frame_sink(Some(f64::NAN));
+    mock.fail_requests(500, 1);
+    sink.send(Frame::fill(Color::RED)).await.unwrap();
+    let error = loop {
+        if let Err(error) = future::poll_fn(|cx| sink.poll_ready_unpin(cx)).await {
+            break error;
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    };
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::InternalServerError, .. }));
+
+    // The error is only reported once
+    sink.send(Frame::fill(Color::GREEN)).await.unwrap();
+    sink.close().await.unwrap();
+    assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::GREEN)]);
+}
@@ -0,0 +1,346 @@
+mod common;
+
+use std::{collections::HashMap, sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::Duration};
+
+use futures::StreamExt;
+use lighthouse_client::{protocol::{path, Authentication, ClientMessage, Color, Frame, PathError, ServerMessage, StatusCode, Value, Verb}, EarlyMessageLimits, Error, Interceptor, Keepalive, LighthouseBuilder, MockLighthouse, RequestAction, RequestContext, RequestOptions, ResponseAction, RetryPolicy, TokioSpawner};
+
+use common::{connect, connect_with};
+
+#[tokio::test]
+async fn records_frames() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    lh.put_model(Frame::fill(Color::RED)).await.unwrap();
+    lh.put_model(Frame::fill(Color::GREEN)).await.unwrap();
+    // Frames rejected by the server are not recorded
+    mock.fail_requests(500, 1);
+    assert!(lh.put_model(Frame::fill(Color::BLUE)).await.is_err());
+    assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::RED), Frame::fill(Color::GREEN)]);
+}
+
+#[tokio::test]
+async fn resource_tree() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    lh.mkdir("/dir").await.unwrap();
+    lh.post("/dir/a", 42).await.unwrap();
+    assert_eq!(lh.get::<i32>("/dir/a").await.unwrap().payload, 42);
+    assert!(lh.list("/dir").await.unwrap().payload.entries.contains_key("a"));
+    let error = lh.put("/dir/b", 1).await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::NotFound, .. }));
+    assert_eq!(error.context(), Some(&RequestContext::new(&Verb::Put, &path!["dir", "b"], 4)));
+    assert_eq!(error.to_string(), "PUT /dir/b (request id 4)");
+    assert!(std::error::Error::source(&error).unwrap().to_string().starts_with("Server error: 404 Not Found"));
+    lh.delete("/dir/a").await.unwrap();
+    assert_eq!(mock.get("/dir/a"), None);
+}
+
+#[tokio::test]
+async fn typed_resources() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    let source = lh.resource::<String>("/source").unwrap();
+    let dest = lh.resource::<String>("/dest").unwrap();
+    source.post("a".to_owned()).await.unwrap();
+    dest.post("b".to_owned()).await.unwrap();
+    source.link(dest.path()).await.unwrap();
+    source.put("c".to_owned()).await.unwrap();
+    assert_eq!(dest.get().await.unwrap().payload, "c");
+}
+
+#[tokio::test]
+async fn rejects_invalid_paths() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    assert!(matches!(lh.post("/a//b", 1).await, Err(Error::Path(PathError::EmptySegment))));
+    assert!(matches!(lh.resource::<i32>(["a", ".."]), Err(Error::Path(PathError::IllegalSegment(_)))));
+
+    // Invalid paths only fail their own request of a batch
+    let results = lh.batch().post("/a", 1).post("/../b", 2).send().await;
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(Error::Path(_))));
+    assert_eq!(mock.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn rejects_invalid_usernames() {
+    let mock = MockLighthouse::new();
+    let lh = LighthouseBuilder::new(Authentication::new("", "token"))
+        .spawner::<TokioSpawner>()
+        .build(mock.connect())
+        .unwrap();
+    assert!(matches!(lh.model(), Err(Error::Path(PathError::EmptySegment))));
+    assert!(matches!(lh.put_model(Frame::fill(Color::RED)).await, Err(Error::Path(_))));
+    assert!(lh.stream_input().await.is_err());
+    assert!(mock.requests().is_empty());
+}
+
+#[tokio::test]
+async fn sends_meta() {
+    let mock = MockLighthouse::new();
+    let lh = connect_with(&mock, |builder| builder.meta("client", "test").meta("trace", "default"));
+    let options = RequestOptions::new().with_meta("trace", "1234");
+    let response: ServerMessage<()> = lh.perform_with_options(&Verb::Post, "/a", 1, &options).await.unwrap();
+    assert_eq!(response.meta, HashMap::from([("client".to_owned(), "test".to_owned()), ("trace".to_owned(), "1234".to_owned())]));
+    assert_eq!(mock.requests()[0].meta, response.meta);
+}
+
+#[tokio::test]
+async fn retries_failed_requests() {
+    let mock = MockLighthouse::new();
+    mock.put("/a", 0);
+    let retry = RetryPolicy { initial_delay: Duration::from_millis(1), ..Default::default() };
+    let lh = connect_with(&mock, |builder| builder.retry(retry));
+    mock.fail_requests(503, 2);
+    lh.put("/a", 1).await.unwrap();
+    assert_eq!(mock.requests().len(), 3);
+
+    mock.fail_requests(503, 3);
+    let error = lh.put("/a", 2).await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::ServiceUnavailable, attempts: 3, .. }));
+
+    // Non-idempotent verbs are not retried
+    mock.fail_requests(503, 1);
+    let error = lh.post("/a", 3).await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::ServiceUnavailable, attempts: 1, .. }));
+    assert_eq!(mock.get("/a"), Some(Value::from(1)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn retries_without_delay() {
+    let mock = MockLighthouse::new();
+    mock.put("/a", 0);
+    let retry = RetryPolicy { initial_delay: Duration::ZERO, ..Default::default() };
+    // Fail instead of hanging if a retry loses its response
+    let lh = connect_with(&mock, |builder| builder.retry(retry).timeout(Duration::from_secs(5)));
+    for i in 0..500 {
+        mock.fail_requests(503, 2);
+        lh.put("/a", i).await.unwrap();
+    }
+    assert_eq!(mock.requests().len(), 1500);
+    assert_eq!(mock.get("/a"), Some(Value::from(499)));
+}
+
+#[tokio::test]
+async fn times_out() {
+    let mock = MockLighthouse::new();
+    let lh = connect_with(&mock, |builder| builder.timeout(Duration::from_millis(20)));
+    mock.ignore_requests(1);
+    let error = lh.post("/a", 1).await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Timeout));
+
+    // The default timeout is shared by all handles
+    let other = lh.clone();
+    other.set_default_timeout(Some(Duration::from_secs(5)));
+    assert_eq!(lh.default_timeout(), Some(Duration::from_secs(5)));
+    lh.post("/a", 2).await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_pending_requests_on_disconnect() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    mock.ignore_requests(1);
+    let post = tokio::spawn({
+        let lh = lh.clone();
+        async move { lh.post("/a", 1).await }
+    });
+    while mock.requests().is_empty() {
+        tokio::task::yield_now().await;
+    }
+    mock.disconnect_all();
+    let error = post.await.unwrap().unwrap_err();
+    assert!(matches!(error.without_context(), Error::ConnectionClosed));
+}
+
+#[tokio::test]
+async fn limits_early_messages() {
+    let mock = MockLighthouse::new();
+    mock.put("/a", 0);
+    let lh = connect(&mock);
+    lh.set_early_message_limits(EarlyMessageLimits { per_request: 1, total: 2, ..Default::default() });
+    // The second message exceeds the per-request limit and does not count
+    // towards the total, the fourth one exceeds the total limit
+    mock.notify_request(1, 200, "a1");
+    mock.notify_request(1, 200, "a2");
+    mock.notify_request(2, 200, "b1");
+    mock.notify_request(3, 200, "c1");
+    // The response arrives after the early messages, which are stored by then
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from(0));
+
+    // Requests with a stored early message take it as their response
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from("a1"));
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from("b1"));
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from(0));
+}
+
+#[tokio::test]
+async fn expires_early_messages() {
+    let mock = MockLighthouse::new();
+    mock.put("/a", 0);
+    let lh = connect(&mock);
+    lh.set_early_message_limits(EarlyMessageLimits { expiry: Duration::from_millis(20), ..Default::default() });
+    mock.notify_request(1, 200, "a");
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from(0));
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    // Storing another early message discards the expired ones
+    mock.notify_request(2, 200, "b");
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from(0));
+    assert_eq!(lh.get::<Value>("/a").await.unwrap().payload, Value::from("b"));
+}
+
+#[tokio::test]
+async fn reports_unacknowledged_errors() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    let mut errors = lh.unacknowledged_errors();
+    lh.put_model_nowait(Frame::fill(Color::RED)).await.unwrap();
+    lh.perform_nowait(&Verb::Put, "/missing/a", 1).await.unwrap();
+    let error = errors.next().await.unwrap();
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::NotFound, .. }));
+    assert_eq!(error.context(), Some(&RequestContext::new(&Verb::Put, &path!["missing", "a"], 1)));
+    assert_eq!(mock.frames("alice"), vec![Frame::fill(Color::RED)]);
+    assert_eq!(lh.stats().in_flight, 0);
+}
+
+#[tokio::test]
+async fn expires_unanswered_nowait_requests() {
+    let mock = MockLighthouse::new();
+    let keepalive = Keepalive { interval: Duration::from_millis(5), ..Default::default() };
+    let lh = connect_with(&mock, |builder| builder.timeout(Duration::from_millis(20)).keepalive(Some(keepalive)));
+    mock.ignore_requests(1);
+    lh.perform_nowait(&Verb::Post, "/a", 1).await.unwrap();
+    assert_eq!(lh.stats().in_flight, 1);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(lh.stats().in_flight, 0);
+}
+
+#[tokio::test]
+async fn releases_permits_of_unanswered_nowait_requests() {
+    let mock = MockLighthouse::new();
+    let keepalive = Keepalive { interval: Duration::from_millis(5), ..Default::default() };
+    let lh = connect_with(&mock, |builder| builder.max_in_flight(1).timeout(Duration::from_millis(20)).keepalive(Some(keepalive)));
+    mock.ignore_requests(1);
+    lh.perform_nowait(&Verb::Post, "/a", 1).await.unwrap();
+    // Waits for the unanswered request to expire rather than forever
+    let options = RequestOptions::new().with_timeout(Duration::from_secs(5));
+    let _: ServerMessage<()> = lh.perform_with_options(&Verb::Post, "/b", 2, &options).await.unwrap();
+    assert_eq!(mock.get("/b"), Some(Value::from(2)));
+}
+
+#[tokio::test]
+async fn reports_warnings() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    let mut warnings = lh.warnings();
+    mock.warn_next("Deprecated");
+    let response = lh.post("/a", 1).await.unwrap();
+    assert_eq!(response.warnings, vec!["Deprecated".to_owned()]);
+    assert_eq!(warnings.next().await.unwrap().warnings, response.warnings);
+}
+
+#[tokio::test]
+async fn limits_requests_in_flight() {
+    let mock = MockLighthouse::new();
+    // A limit of 0 is treated as 1 rather than blocking every request
+    let lh = connect_with(&mock, |builder| builder.max_in_flight(0));
+    assert_eq!(lh.max_in_flight(), Some(1));
+    mock.ignore_requests(1);
+    let first = tokio::spawn({
+        let lh = lh.clone();
+        async move {
+            let options = RequestOptions::new().with_timeout(Duration::from_millis(50));
+            lh.perform_with_options::<_, ()>(&Verb::Post, "/a", 1, &options).await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let second = tokio::spawn({
+        let lh = lh.clone();
+        async move { lh.post("/b", 2).await }
+    });
+
+    // The second request waits until the first one times out
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(mock.requests().len(), 1);
+    assert!(matches!(first.await.unwrap().unwrap_err().without_context(), Error::Timeout));
+    second.await.unwrap().unwrap();
+    assert_eq!(mock.get("/b"), Some(Value::from(2)));
+}
+
+#[tokio::test]
+async fn pipelines_batches() {
+    let mock = MockLighthouse::new();
+    let lh = connect_with(&mock, |builder| builder.max_in_flight(2));
+    let mut batch = lh.batch().mkdir("/test");
+    for i in 0..5 {
+        batch = batch.post(format!("/test/{i}"), i);
+    }
+    let results = batch.get("/test/3").get("/missing").window(4).send().await;
+    assert_eq!(results.len(), 8);
+    assert!(results[..6].iter().all(|result| result.is_ok()));
+    assert_eq!(results[6].as_ref().unwrap().payload, Value::from(3));
+    assert!(matches!(results[7].as_ref().unwrap_err().without_context(), Error::Server { code: StatusCode::NotFound, .. }));
+
+    // Requests are sent in order
+    let paths: Vec<_> = mock.requests().into_iter().map(|request| request.path.join("/")).collect();
+    assert_eq!(paths[..3], ["test", "test/0", "test/1"]);
+    assert_eq!(lh.stats().in_flight, 0);
+}
+
+#[tokio::test]
+async fn intercepts_messages() {
+    struct Tenant;
+
+    impl Interceptor for Tenant {
+        fn on_request(&self, request: &mut ClientMessage<Value>) -> RequestAction {
+            request.path.insert(0, "tenant".to_owned());
+            request.meta.insert("tenant".to_owned(), "a".to_owned());
+            RequestAction::Continue
+        }
+    }
+
+    struct Faults(Arc<AtomicUsize>);
+
+    impl Interceptor for Faults {
+        fn on_request(&self, request: &mut ClientMessage<Value>) -> RequestAction {
+            if request.path.last().is_some_and(|name| name == "faulty") {
+                RequestAction::Respond(ServerMessage {
+                    code: StatusCode::ServiceUnavailable,
+                    request_id: None,
+                    warnings: Vec::new(),
+                    response: None,
+                    meta: HashMap::new(),
+                    payload: Value::Nil,
+                })
+            } else {
+                RequestAction::Continue
+            }
+        }
+
+        fn on_response(&self, _response: &mut ServerMessage<Value>) -> ResponseAction {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            ResponseAction::Continue
+        }
+    }
+
+    let mock = MockLighthouse::new();
+    mock.put("/tenant/a", 0);
+    let responses = Arc::new(AtomicUsize::new(0));
+    let lh = connect_with(&mock, |builder| builder.interceptor(Tenant).interceptor(Faults(responses.clone())));
+    let response = lh.put("/a", 1).await.unwrap();
+    assert_eq!(response.meta.get("tenant").map(String::as_str), Some("a"));
+    assert_eq!(mock.get("/tenant/a"), Some(Value::from(1)));
+    assert_eq!(responses.load(Ordering::Relaxed), 1);
+
+    // Short-circuited requests are not sent and their responses only pass
+    // through the preceding interceptors
+    let error = lh.put("/faulty", 2).await.unwrap_err();
+    assert!(matches!(error.without_context(), Error::Server { code: StatusCode::ServiceUnavailable, .. }));
+    assert_eq!(mock.requests().len(), 1);
+    assert_eq!(responses.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,144 @@
+mod common;
+
+use std::time::Duration;
+
+use futures::StreamExt;
+use lighthouse_client::{protocol::{path, Authentication, EventSource, InputEvent, KeyEvent, KeyModifiers, Value, Verb}, Error, LighthouseBuilder, MockLighthouse, ReconnectPolicy, RequestContext, TokioSpawner};
+
+use common::connect;
+
+fn key_event(code: &str) -> InputEvent {
+    InputEvent::Key(KeyEvent {
+        source: EventSource::Int(0),
+        down: true,
+        repeat: false,
+        code: code.to_owned(),
+        modifiers: KeyModifiers::default(),
+    })
+}
+
+#[tokio::test]
+async fn streams_input() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    let mut input = lh.stream_input().await.unwrap();
+    mock.inject_input("alice", key_event("KeyA"));
+    assert_eq!(input.next().await.unwrap().unwrap().payload, key_event("KeyA"));
+}
+
+#[tokio::test]
+async fn delivers_notifications() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    let mut notifications = lh.notifications();
+    mock.notify(200, "Maintenance at noon");
+    let notification = notifications.next().await.unwrap();
+    assert_eq!(notification.request_id, None);
+    assert_eq!(notification.payload, Value::from("Maintenance at noon"));
+}
+
+#[tokio::test]
+async fn shares_streams() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    let mut first = lh.stream_input().await.unwrap();
+    let mut second = lh.stream_input().await.unwrap();
+    mock.inject_input("alice", key_event("KeyA"));
+    assert_eq!(first.next().await.unwrap().unwrap().payload, key_event("KeyA"));
+    assert_eq!(second.next().await.unwrap().unwrap().payload, key_event("KeyA"));
+    let verbs = || mock.requests().into_iter().map(|request| request.verb).collect::<Vec<_>>();
+    assert_eq!(verbs(), vec![Verb::Stream]);
+
+    // The subscription is only stopped once the last subscriber is gone
+    drop(first);
+    mock.inject_input("alice", key_event("KeyB"));
+    assert_eq!(second.next().await.unwrap().unwrap().payload, key_event("KeyB"));
+    drop(second);
+    while verbs().len() < 2 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    assert_eq!(verbs(), vec![Verb::Stream, Verb::Stop]);
+}
+
+#[tokio::test]
+async fn drops_oldest_messages_by_default() {
+    let mock = MockLighthouse::new();
+    mock.put("/counter", 0);
+    let lh = connect(&mock);
+    let mut counter = lh.stream::<_, i64>("/counter", ()).await.unwrap();
+    for i in 1..=10 {
+        mock.put("/counter", i);
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let mut payloads = Vec::new();
+    while payloads.last() != Some(&10) {
+        payloads.push(counter.next().await.unwrap().unwrap().payload);
+    }
+    assert_eq!(payloads, vec![7, 8, 9, 10]);
+}
+
+#[tokio::test]
+async fn keeps_input_events_by_default() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    let mut input = lh.stream_input().await.unwrap();
+    let codes: Vec<_> = (0..10).map(|i| format!("Key{i}")).collect();
+    for code in &codes {
+        mock.inject_input("alice", key_event(code));
+    }
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    for code in &codes {
+        assert_eq!(input.next().await.unwrap().unwrap().payload, key_event(code));
+    }
+}
+
+#[tokio::test]
+async fn ends_streams_on_disconnect() {
+    let mock = MockLighthouse::new();
+    mock.put("/counter", 0);
+    let lh = connect(&mock);
+    let mut counter = lh.stream::<_, Value>("/counter", ()).await.unwrap();
+    assert_eq!(counter.next().await.unwrap().unwrap().payload, Value::from(0));
+    mock.disconnect_all();
+    let error = counter.next().await.unwrap().unwrap_err();
+    assert!(matches!(error.without_context(), Error::ConnectionClosed));
+}
+
+#[tokio::test]
+async fn resubscribes_after_reconnect() {
+    let mock = MockLighthouse::new();
+    mock.put("/counter", 0);
+    let connector = mock.clone();
+    let policy = ReconnectPolicy { initial_delay: Duration::from_millis(10), ..Default::default() };
+    let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+        .reconnect(Some(policy))
+        .spawner::<TokioSpawner>()
+        .build_with_connector(mock.connect(), move || {
+            let mock = connector.clone();
+            async move { Ok(mock.connect()) }
+        })
+        .unwrap();
+    let mut counter = lh.stream::<_, Value>("/counter", ()).await.unwrap();
+    assert_eq!(counter.next().await.unwrap().unwrap().payload, Value::from(0));
+    mock.disconnect_all();
+    // The resubscription yields the current value again
+    assert_eq!(counter.next().await.unwrap().unwrap().payload, Value::from(0));
+    mock.put("/counter", 1);
+    assert_eq!(counter.next().await.unwrap().unwrap().payload, Value::from(1));
+}
+
+#[tokio::test]
+async fn attaches_context_to_stream_errors() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    mock.disconnect_all();
+    // The state stream ends once the connection is closed for good
+    lh.state().for_each(|_| async {}).await;
+    let Err(error) = lh.stream::<_, Value>("/counter", ()).await else { panic!("Streamed from a closed connection") };
+    assert!(matches!(error.without_context(), Error::ConnectionClosed));
+    assert_eq!(error.context(), Some(&RequestContext::new(&Verb::Stream, &path!["counter"], 0)));
+    assert_eq!(error.to_string(), "STREAM /counter (request id 0)");
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use std::{io::{self, Write}, sync::{Arc, Mutex}, time::Duration};
+
+use futures::StreamExt;
+use lighthouse_client::{protocol::{Authentication, Color, Frame, Verb}, ConnectionState, Keepalive, LighthouseBuilder, MockLighthouse, Session, SessionEvent, SessionRecorder, TokioSpawner};
+
+use common::{connect, connect_with};
+
+#[tokio::test]
+async fn collects_stats() {
+    let mock = MockLighthouse::new();
+    mock.add_user("alice");
+    let lh = connect(&mock);
+    lh.put_model(Frame::fill(Color::RED)).await.unwrap();
+    lh.put_model(Frame::fill(Color::GREEN)).await.unwrap();
+    let input = lh.stream_input().await.unwrap();
+    let stats = lh.stats();
+    assert_eq!(stats.requests.get(&Verb::Put), Some(&2));
+    assert_eq!(stats.requests.get(&Verb::Stream), Some(&1));
+    assert_eq!(stats.put_latency.count(), 2);
+    assert_eq!(stats.frames, 2);
+    assert!(stats.frames_per_second > 0.0);
+    assert_eq!((stats.streams, stats.stream_subscribers), (1, 1));
+    assert!(stats.bytes_sent > 0);
+    assert!(stats.bytes_received > 0);
+    assert_eq!(stats.decode_errors, 0);
+    drop(input);
+    assert_eq!(lh.stats().stream_subscribers, 0);
+}
+
+#[tokio::test]
+async fn measures_latency() {
+    let mock = MockLighthouse::new();
+    let keepalive = Keepalive { interval: Duration::from_millis(5), ..Default::default() };
+    let lh = connect_with(&mock, |builder| builder.keepalive(Some(keepalive)));
+    assert_eq!(lh.latency(), None);
+    let pong = async {
+        while lh.latency().is_none() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    };
+    tokio::time::timeout(Duration::from_secs(1), pong).await.expect("No pong within a second");
+}
+
+#[tokio::test]
+async fn drops_connections_without_pongs() {
+    let mock = MockLighthouse::new();
+    mock.ignore_pings(true);
+    let keepalive = Keepalive { interval: Duration::from_millis(5), timeout: Duration::from_millis(20) };
+    let lh = connect_with(&mock, |builder| builder.keepalive(Some(keepalive)));
+    let states = lh.state().collect::<Vec<_>>().await;
+    assert!(matches!(&states[..], [ConnectionState::Connected, ConnectionState::Disconnected { reason }, ConnectionState::Closed] if reason.contains("Keepalive")));
+    assert_eq!(lh.latency(), None);
+}
+
+#[tokio::test]
+async fn reports_only_errors_ending_the_connection() {
+    let mock = MockLighthouse::new();
+    let lh = connect(&mock);
+    // 0xc1 is never used in MessagePack
+    mock.notify_raw(vec![0xc1]);
+    lh.post("/a", 1).await.unwrap();
+    assert_eq!(lh.stats().decode_errors, 1);
+    mock.disconnect_all();
+    let states = lh.state().collect::<Vec<_>>().await;
+    assert!(matches!(&states[..], [ConnectionState::Connected, ConnectionState::Disconnected { reason }, ConnectionState::Closed] if reason == "The connection was closed"));
+}
+
+#[tokio::test]
+async fn records_and_replays_sessions() {
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mock = MockLighthouse::new();
+    let buffer = Buffer::default();
+    let lh = connect_with(&mock, |builder| builder.record(SessionRecorder::new(buffer.clone())));
+    lh.post("/a", 42).await.unwrap();
+    assert_eq!(lh.get::<i32>("/a").await.unwrap().payload, 42);
+    lh.close().await.unwrap();
+
+    let session = Session::read_from(buffer.0.lock().unwrap().as_slice()).unwrap();
+    assert_eq!(session.entries.len(), 4);
+    let SessionEvent::Sent(request) = &session.entries[0].event else { panic!("Expected a sent message") };
+    assert_eq!(request.authentication.token, "<redacted>");
+
+    // Replaying yields the recorded responses without a server
+    let lh = LighthouseBuilder::new(Authentication::new("alice", "token"))
+        .spawner::<TokioSpawner>()
+        .build(session.replay())
+        .unwrap();
+    lh.post("/a", 42).await.unwrap();
+    assert_eq!(lh.get::<i32>("/a").await.unwrap().payload, 42);
+}
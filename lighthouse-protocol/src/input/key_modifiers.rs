@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 /// A keyboard event.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct KeyModifiers {
     /// Whether the alt key is held.
@@ -13,14 +13,3 @@ pub struct KeyModifiers {
     /// Whether the shiftKey key is held.
     pub shift: bool,
 }
-
-impl Default for KeyModifiers {
-    fn default() -> Self {
-        Self {
-            alt: false,
-            ctrl: false,
-            meta: false,
-            shift: false,
-        }
-    }
-}
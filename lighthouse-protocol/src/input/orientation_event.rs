@@ -23,8 +23,8 @@ pub struct OrientationEvent {
 impl OrientationEvent {
     /// The approximate direction (outside of a small deadzone) for a phone tilted against a flat surface.
     pub fn direction(&self) -> Option<Direction> {
-        let Some(beta) = self.beta else { return None };
-        let Some(gamma) = self.gamma else { return None };
+        let beta = self.beta?;
+        let gamma = self.gamma?;
 
         let deadzone_radius: f64 = 10.0;
         if beta.abs().max(gamma.abs()) < deadzone_radius {
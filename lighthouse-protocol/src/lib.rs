@@ -4,7 +4,9 @@ mod constants;
 mod frame;
 mod input;
 mod payload;
+mod resource_path;
 mod server_message;
+mod status_code;
 mod utils;
 mod verb;
 
@@ -14,7 +16,9 @@ pub use constants::*;
 pub use frame::*;
 pub use input::*;
 pub use payload::*;
+pub use resource_path::*;
 pub use server_message::*;
+pub use status_code::*;
 pub use utils::*;
 pub use verb::*;
 
@@ -5,6 +5,7 @@ use crate::{Frame, LegacyInputEvent};
 /// The payload of a model message.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
 pub enum Model {
     Frame(Frame),
     InputEvent(LegacyInputEvent),
@@ -0,0 +1,261 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The path to a resource or directory on the lighthouse, e.g.
+/// `/user/alice/model`.
+///
+/// Paths consist of segments, none of which may be empty, `.`, `..` or
+/// contain a `/`. Valid paths can be created by parsing a string, from
+/// segments or via the [`path!`](crate::path) macro.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+#[serde(transparent)]
+pub struct ResourcePath {
+    segments: Vec<String>,
+}
+
+/// An error from creating an invalid [`ResourcePath`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathError {
+    /// A segment was empty, e.g. in `/user//model`.
+    EmptySegment,
+    /// A segment was `.` or `..` or contained a `/`.
+    IllegalSegment(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EmptySegment => write!(f, "Resource paths may not contain empty segments"),
+            Self::IllegalSegment(segment) => write!(f, "Illegal resource path segment: {:?}", segment),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+impl ResourcePath {
+    /// The root directory.
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Creates a path from the given segments.
+    pub fn new(segments: impl IntoIterator<Item = impl Into<String>>) -> Result<Self, PathError> {
+        let segments = segments.into_iter().map(Into::into).collect::<Vec<String>>();
+        for segment in &segments {
+            validate_segment(segment)?;
+        }
+        Ok(Self { segments })
+    }
+
+    /// Parses a path such as `/user/alice/model`. The leading and a trailing
+    /// slash are optional, `/` and the empty string denote the root.
+    pub fn parse(s: &str) -> Result<Self, PathError> {
+        let s = s.strip_prefix('/').unwrap_or(s);
+        let s = s.strip_suffix('/').unwrap_or(s);
+        if s.is_empty() {
+            return Ok(Self::root());
+        }
+        Self::new(s.split('/'))
+    }
+
+    /// Creates the path of the given child of this directory.
+    pub fn join(&self, segment: impl Into<String>) -> Result<Self, PathError> {
+        let segment = segment.into();
+        validate_segment(&segment)?;
+        let mut segments = self.segments.clone();
+        segments.push(segment);
+        Ok(Self { segments })
+    }
+
+    /// Creates the path of the given descendant of this directory.
+    pub fn concat(&self, path: &ResourcePath) -> Self {
+        Self { segments: self.segments.iter().chain(&path.segments).cloned().collect() }
+    }
+
+    /// The parent directory, or `None` for the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Self { segments: parent.to_vec() })
+    }
+
+    /// The last segment, or `None` for the root.
+    pub fn file_name(&self) -> Option<&str> {
+        self.segments.last().map(String::as_str)
+    }
+
+    /// Whether this is the root directory.
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The segments of this path.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    /// Converts this path into its segments.
+    pub fn into_segments(self) -> Vec<String> {
+        self.segments
+    }
+}
+
+/// Fails if the given segment is not allowed in a path.
+fn validate_segment(segment: &str) -> Result<(), PathError> {
+    if segment.is_empty() {
+        Err(PathError::EmptySegment)
+    } else if segment == "." || segment == ".." || segment.contains('/') {
+        Err(PathError::IllegalSegment(segment.to_owned()))
+    } else {
+        Ok(())
+    }
+}
+
+impl fmt::Display for ResourcePath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "/");
+        }
+        for segment in &self.segments {
+            write!(f, "/{}", segment)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ResourcePath {
+    type Err = PathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourcePath {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        let segments = Vec::<String>::deserialize(deserializer)?;
+        Self::new(segments).map_err(serde::de::Error::custom)
+    }
+}
+
+// The following conversions allow passing string literals and segment lists
+// wherever an `impl TryInto<ResourcePath>` is expected, reporting invalid
+// paths rather than panicking.
+
+impl From<&ResourcePath> for ResourcePath {
+    fn from(path: &ResourcePath) -> Self {
+        path.clone()
+    }
+}
+
+impl TryFrom<&str> for ResourcePath {
+    type Error = PathError;
+
+    /// Parses the given path.
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<String> for ResourcePath {
+    type Error = PathError;
+
+    /// Parses the given path.
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+impl TryFrom<&String> for ResourcePath {
+    type Error = PathError;
+
+    /// Parses the given path.
+    fn try_from(s: &String) -> Result<Self, Self::Error> {
+        Self::parse(s)
+    }
+}
+
+impl<S> TryFrom<&[S]> for ResourcePath where S: AsRef<str> {
+    type Error = PathError;
+
+    /// Creates a path from the given segments.
+    fn try_from(segments: &[S]) -> Result<Self, Self::Error> {
+        Self::new(segments.iter().map(|s| s.as_ref()))
+    }
+}
+
+impl<S, const N: usize> TryFrom<&[S; N]> for ResourcePath where S: AsRef<str> {
+    type Error = PathError;
+
+    /// Creates a path from the given segments.
+    fn try_from(segments: &[S; N]) -> Result<Self, Self::Error> {
+        Self::try_from(segments.as_slice())
+    }
+}
+
+impl<S, const N: usize> TryFrom<[S; N]> for ResourcePath where S: AsRef<str> {
+    type Error = PathError;
+
+    /// Creates a path from the given segments.
+    fn try_from(segments: [S; N]) -> Result<Self, Self::Error> {
+        Self::try_from(segments.as_slice())
+    }
+}
+
+impl<S> TryFrom<Vec<S>> for ResourcePath where S: AsRef<str> {
+    type Error = PathError;
+
+    /// Creates a path from the given segments.
+    fn try_from(segments: Vec<S>) -> Result<Self, Self::Error> {
+        Self::try_from(segments.as_slice())
+    }
+}
+
+/// Creates a [`ResourcePath`] from the given segments, e.g.
+/// `path!["user", username, "model"]`. Without segments, this is the root.
+///
+/// # Panics
+///
+/// If a segment is invalid.
+#[macro_export]
+macro_rules! path {
+    ($($segment:expr),* $(,)?) => {
+        $crate::ResourcePath::new((&[$(::std::convert::AsRef::<str>::as_ref(&$segment)),*] as &[&str]).iter().copied())
+            .unwrap_or_else(|e| panic!("Invalid resource path: {}", e))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PathError, ResourcePath};
+
+    #[test]
+    fn parse_and_display() {
+        let path = ResourcePath::parse("/user/alice/model").unwrap();
+        assert_eq!(path.segments(), ["user", "alice", "model"]);
+        assert_eq!(path.to_string(), "/user/alice/model");
+        assert_eq!(ResourcePath::parse("user/alice/").unwrap().to_string(), "/user/alice");
+        assert_eq!(ResourcePath::parse("/").unwrap(), ResourcePath::root());
+        assert_eq!(ResourcePath::root().to_string(), "/");
+    }
+
+    #[test]
+    fn invalid_segments() {
+        assert_eq!(ResourcePath::parse("/user//model"), Err(PathError::EmptySegment));
+        assert_eq!(ResourcePath::parse("/user/../model"), Err(PathError::IllegalSegment("..".to_owned())));
+        assert_eq!(ResourcePath::root().join("a/b"), Err(PathError::IllegalSegment("a/b".to_owned())));
+        assert_eq!(ResourcePath::try_from(vec!["user", ""]), Err(PathError::EmptySegment));
+    }
+
+    #[test]
+    fn navigation() {
+        let username = "alice".to_owned();
+        let path = path!["user", username, "model"];
+        assert_eq!(path, ResourcePath::try_from("/user/alice/model").unwrap());
+        assert_eq!(path.file_name(), Some("model"));
+        assert_eq!(path.parent().unwrap().join("input").unwrap(), path!["user", "alice", "input"]);
+        assert_eq!(path!().parent(), None);
+        assert_eq!(path!["user"].concat(&path!["alice", "model"]), path!["user", "alice", "model"]);
+    }
+}
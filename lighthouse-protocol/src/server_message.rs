@@ -1,21 +1,38 @@
+use std::collections::HashMap;
+
 use serde::{Serialize, Deserialize};
-use crate::{Value, ValueError};
+use crate::{StatusCode, Value, ValueError};
 
 /// A message originating from the lighthouse server.
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct ServerMessage<P> {
     #[serde(rename = "RNUM")]
-    pub code: i32,
+    pub code: StatusCode,
     #[serde(rename = "REID")]
     pub request_id: Option<i32>,
     #[serde(rename = "WARNINGS", skip_serializing_if = "Vec::is_empty", default)]
     pub warnings: Vec<String>,
     #[serde(rename = "RESPONSE")]
     pub response: Option<String>,
+    #[serde(rename = "META", skip_serializing_if = "HashMap::is_empty", default)]
+    pub meta: HashMap<String, String>,
     #[serde(rename = "PAYL")]
     pub payload: P,
 }
 
+impl<P> ServerMessage<P> {
+    pub fn map_payload<Q>(self, f: impl FnOnce(P) -> Q) -> ServerMessage<Q> {
+        ServerMessage {
+            code: self.code,
+            request_id: self.request_id,
+            warnings: self.warnings,
+            response: self.response,
+            meta: self.meta,
+            payload: f(self.payload),
+        }
+    }
+}
+
 impl ServerMessage<Value> {
     pub fn decode_payload<R>(self) -> Result<ServerMessage<R>, ValueError>
     where
@@ -25,6 +42,7 @@ impl ServerMessage<Value> {
             request_id: self.request_id,
             warnings: self.warnings,
             response: self.response,
+            meta: self.meta,
             payload: rmpv::ext::from_value(self.payload)?,
         })
     }
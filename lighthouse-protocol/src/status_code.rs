@@ -0,0 +1,184 @@
+use std::{fmt, hash::{Hash, Hasher}};
+
+use serde::{Deserialize, Serialize};
+
+/// The status code of a response, following the HTTP status codes.
+/// Codes without a dedicated variant are represented by [`StatusCode::Other`],
+/// codes are compared by their numeric value.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(from = "i32", into = "i32")]
+pub enum StatusCode {
+    Ok,
+    Created,
+    Accepted,
+    NoContent,
+    BadRequest,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    RequestTimeout,
+    Conflict,
+    ImATeapot,
+    TooManyRequests,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    Other(i32),
+}
+
+impl StatusCode {
+    /// The numeric code.
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Ok => 200,
+            Self::Created => 201,
+            Self::Accepted => 202,
+            Self::NoContent => 204,
+            Self::BadRequest => 400,
+            Self::Unauthorized => 401,
+            Self::Forbidden => 403,
+            Self::NotFound => 404,
+            Self::MethodNotAllowed => 405,
+            Self::RequestTimeout => 408,
+            Self::Conflict => 409,
+            Self::ImATeapot => 418,
+            Self::TooManyRequests => 429,
+            Self::InternalServerError => 500,
+            Self::NotImplemented => 501,
+            Self::BadGateway => 502,
+            Self::ServiceUnavailable => 503,
+            Self::GatewayTimeout => 504,
+            Self::Other(code) => code,
+        }
+    }
+
+    /// A short description of the code, if it is known.
+    pub fn reason(self) -> Option<&'static str> {
+        Some(match self {
+            Self::Ok => "OK",
+            Self::Created => "Created",
+            Self::Accepted => "Accepted",
+            Self::NoContent => "No Content",
+            Self::BadRequest => "Bad Request",
+            Self::Unauthorized => "Unauthorized",
+            Self::Forbidden => "Forbidden",
+            Self::NotFound => "Not Found",
+            Self::MethodNotAllowed => "Method Not Allowed",
+            Self::RequestTimeout => "Request Timeout",
+            Self::Conflict => "Conflict",
+            Self::ImATeapot => "I'm a teapot",
+            Self::TooManyRequests => "Too Many Requests",
+            Self::InternalServerError => "Internal Server Error",
+            Self::NotImplemented => "Not Implemented",
+            Self::BadGateway => "Bad Gateway",
+            Self::ServiceUnavailable => "Service Unavailable",
+            Self::GatewayTimeout => "Gateway Timeout",
+            Self::Other(_) => return None,
+        })
+    }
+
+    /// Whether the code indicates success, i.e. is a 2xx code.
+    pub fn is_success(self) -> bool {
+        (200..300).contains(&self.code())
+    }
+
+    /// Whether the code indicates an error caused by the client, i.e. is a
+    /// 4xx code.
+    pub fn is_client_error(self) -> bool {
+        (400..500).contains(&self.code())
+    }
+
+    /// Whether the code indicates an error on the server, i.e. is a 5xx code.
+    pub fn is_server_error(self) -> bool {
+        (500..600).contains(&self.code())
+    }
+
+    /// Whether the code indicates a transient error, i.e. repeating the
+    /// request later may succeed.
+    pub fn is_retriable(self) -> bool {
+        matches!(self.code(), 408 | 429 | 502 | 503 | 504)
+    }
+}
+
+impl From<i32> for StatusCode {
+    fn from(code: i32) -> Self {
+        match code {
+            200 => Self::Ok,
+            201 => Self::Created,
+            202 => Self::Accepted,
+            204 => Self::NoContent,
+            400 => Self::BadRequest,
+            401 => Self::Unauthorized,
+            403 => Self::Forbidden,
+            404 => Self::NotFound,
+            405 => Self::MethodNotAllowed,
+            408 => Self::RequestTimeout,
+            409 => Self::Conflict,
+            418 => Self::ImATeapot,
+            429 => Self::TooManyRequests,
+            500 => Self::InternalServerError,
+            501 => Self::NotImplemented,
+            502 => Self::BadGateway,
+            503 => Self::ServiceUnavailable,
+            504 => Self::GatewayTimeout,
+            code => Self::Other(code),
+        }
+    }
+}
+
+impl From<StatusCode> for i32 {
+    fn from(code: StatusCode) -> Self {
+        code.code()
+    }
+}
+
+impl PartialEq for StatusCode {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for StatusCode {}
+
+impl Hash for StatusCode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+    }
+}
+
+impl PartialEq<i32> for StatusCode {
+    fn eq(&self, other: &i32) -> bool {
+        self.code() == *other
+    }
+}
+
+impl fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.reason() {
+            Some(reason) => write!(f, "{} {}", self.code(), reason),
+            None => write!(f, "{}", self.code()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusCode;
+
+    #[test]
+    fn roundtrips_codes() {
+        for code in [200, 404, 418, 503, 299, 999] {
+            assert_eq!(StatusCode::from(code).code(), code);
+        }
+        assert_eq!(StatusCode::from(404), StatusCode::NotFound);
+        assert_eq!(StatusCode::from(299), StatusCode::Other(299));
+        assert_eq!(StatusCode::Other(404), StatusCode::NotFound);
+        assert!(StatusCode::from(299).is_success());
+        assert!(StatusCode::TooManyRequests.is_retriable());
+        assert!(!StatusCode::NotFound.is_retriable());
+        assert_eq!(StatusCode::NotFound.to_string(), "404 Not Found");
+    }
+}
@@ -1,7 +1,9 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 
 /// A request method.
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Hash, Clone)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum Verb {
     Post,
@@ -18,3 +20,23 @@ pub enum Verb {
     #[serde(untagged)]
     Unknown(String),
 }
+
+impl fmt::Display for Verb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = match self {
+            Self::Post => "POST",
+            Self::Create => "CREATE",
+            Self::Mkdir => "MKDIR",
+            Self::Delete => "DELETE",
+            Self::List => "LIST",
+            Self::Get => "GET",
+            Self::Put => "PUT",
+            Self::Stream => "STREAM",
+            Self::Stop => "STOP",
+            Self::Link => "LINK",
+            Self::Unlink => "UNLINK",
+            Self::Unknown(verb) => verb,
+        };
+        write!(f, "{}", verb)
+    }
+}